@@ -18,7 +18,7 @@ import { setCanvasSize, GRAPH, GRAPH_OVERLAY } from "./canvas.js"
 // using the #[wasm_bindgen] directive in lib.rs.  These names, in turn, must match the tab names listed in the
 // tabConfig object in tabConfig.js
 import init, {
-  rb_porous_absorber, slotted_panel, perforated_panel, microperforated_panel
+  rb_porous_absorber, slotted_panel, perforated_panel, microperforated_panel, set_trace, trace_modules
 } from '../pkg/porous_absorber_calculator.js'
 
 import defineTrace from "./appConfig.js"
@@ -93,6 +93,7 @@ window.updateScreen = TM.updateScreen
 window.updateScreenAndMouseHandler = TM.updateScreenAndMouseHandler
 window.limitMax = TM.limitMax
 window.half = TM.half
+window.belowHalf = TM.belowHalf
 window.double = TM.double
 window.toggleLockAbsDims = TM.toggleLockAbsDims
 window.lockAbsDims = {
@@ -108,4 +109,8 @@ window.perforated_panel = perforated_panel
 window.microperforated_panel = microperforated_panel
 window.configuration = no_op
 
+// Allow WASM debug/trace output to be switched on/off from the browser console
+window.set_trace = set_trace
+window.trace_modules = trace_modules
+
 startWASM()
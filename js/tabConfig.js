@@ -42,7 +42,7 @@ const tabConfig = {
     { id: "panel_thickness_mm", units: "mm", isWasmArg: true, getter: getFloat, setter: setFloat },
     { id: "slot_distance_mm", units: "mm", isWasmArg: true, getter: getFloat, setter: setFloat },
     { id: "slot_width_mm", units: "mm", isWasmArg: true, getter: getFloat, setter: setFloat },
-    { id: "slotted_porosity", units: "each", isWasmArg: true, getter: getInnerHTML, setter: setFloat },
+    { id: "slotted_porosity", units: "each", isWasmArg: false, getter: getInnerHTML, setter: setFloat },
     { id: "absorber_thickness_mm", units: "mm", isWasmArg: true, getter: getInt, setter: setInt },
     { id: "flow_resistivity", units: "rayls/m", isWasmArg: true, getter: getInt, setter: setInt },
     { id: "air_gap_mm", units: "mm", isWasmArg: true, getter: getInt, setter: setInt },
@@ -56,7 +56,7 @@ const tabConfig = {
     { id: "panel_thickness_mm", units: "mm", isWasmArg: true, getter: getFloat, setter: setFloat },
     { id: "repeat_distance_mm", units: "mm", isWasmArg: true, getter: getFloat, setter: setFloat },
     { id: "hole_radius_mm", units: "mm", isWasmArg: true, getter: getFloat, setter: setFloat },
-    { id: "porosity", units: "each", isWasmArg: true, getter: getInnerHTML, setter: setFloat },
+    { id: "porosity", units: "each", isWasmArg: false, getter: getInnerHTML, setter: setFloat },
     { id: "absorber_thickness_mm", units: "mm", isWasmArg: true, getter: getInt, setter: setInt },
     { id: "flow_resistivity", units: "rayls/m", isWasmArg: true, getter: getInt, setter: setInt },
     { id: "air_gap_mm", units: "mm", isWasmArg: true, getter: getInt, setter: setInt },
@@ -70,7 +70,7 @@ const tabConfig = {
     { id: "panel_thickness_mm", units: "mm", isWasmArg: true, getter: getFloat, setter: setFloat },
     { id: "repeat_distance_mm", units: "mm", isWasmArg: true, getter: getFloat, setter: setFloat },
     { id: "hole_radius_mm", units: "mm", isWasmArg: true, getter: getFloat, setter: setFloat },
-    { id: "porosity", units: "each", isWasmArg: true, getter: getInnerHTML, setter: setFloat },
+    { id: "porosity", units: "each", isWasmArg: false, getter: getInnerHTML, setter: setFloat },
     { id: "air_gap_mm", units: "mm", isWasmArg: true, getter: getInt, setter: setInt },
     { id: "angle", units: "°", isWasmArg: true, getter: getInt, setter: setInt },
     { id: "graph_start_freq", units: "Hz", isWasmArg: true, getter: getFloat, setter: setFloat },
@@ -179,23 +179,37 @@ const updateScreenFn = (tabName, rangeName) => {
 //  In the last case, the graph must be redrawn because the canvas size has changed
 const updateScreenAndMouseHandlerFn = tabName =>
   (wasm_response => {
-    // If the WASM function returns an array, then there has been a validation error with one or more of the arguments
-    if (isArray(wasm_response)) {
-      console.error(JSON.stringify(wasm_response, null, 2))
+    // If the WASM function returns an "errors" property, then there has been a validation error with one or more of
+    // the arguments.  Each error identifies the field that failed
+    if (isNotNullOrUndef(wasm_response) && isArray(wasm_response.errors)) {
+      console.error(JSON.stringify(wasm_response.errors, null, 2))
     }
-    // If the non-null wasm_response is an object containing the property "series_data", then a graph has been plotted
-    // and we are getting the chart data back
-    else if (isNotNullOrUndef(wasm_response) && wasm_response.series_data) {
-      // For all tabs except configuration, invert the structure of the wasm_response.series_data array and pass the
-      // result to the canvas overlay mousemove handler
+    // If the "ok" property is an object containing the property "series_data", then a graph has been plotted and we are
+    // getting the chart data back
+    else if (isNotNullOrUndef(wasm_response) && isNotNullOrUndef(wasm_response.ok) && wasm_response.ok.series_data) {
+      // For all tabs except configuration, invert the structure of the series_data array and pass the result to the
+      // canvas overlay mousemove handler
       // The chart_box property defines the bounding box within which the cross hairs should appear
       if (tabName !== "configuration") {
         $id(GRAPH_OVERLAY).onmousemove = canvasMouseOverHandler(
           $id(GRAPH_OVERLAY),
-          wasm_response.chart_box,
-          invertPlotData(wasm_response.series_data),
+          wasm_response.ok.chart_box,
+          invertPlotData(wasm_response.ok.series_data),
         )
       }
+
+      // Values derived by WASM, such as the geometric porosity of a panel, are shown in the display-only field to which
+      // they belong
+      if (isArray(wasm_response.ok.derived_values)) {
+        wasm_response.ok.derived_values
+          .filter(derived => tabConfig[tabName].some(field => field.id === derived.field && !field.isWasmArg))
+          .forEach(derived => $id(derived.field).innerHTML = derived.value.toFixed(6))
+      }
+
+      // Warnings identify argument values whose combination makes the results doubtful
+      if (isArray(wasm_response.ok.warnings) && wasm_response.ok.warnings.length > 0) {
+        console.warn(JSON.stringify(wasm_response.ok.warnings, null, 2))
+      }
     }
     else {
       if (isNotNullOrUndef(wasm_response))
@@ -210,6 +224,8 @@ const updateScreenAndMouseHandlerFn = tabName =>
 // UI slider range limitation
 // Modifier functions for use with the limitMax() function
 const half = val => val / 2.0
+// Leaves panel material between adjacent holes
+const belowHalf = val => val * 0.49
 const double = val => val * 2.0
 
 // Restrict the maximum value of the target UI element to the current value of the source element after applying some
@@ -231,6 +247,7 @@ const toggleLockAbsDims = traceFnBoundary("toggleLockAbsDims", toggleLockAbsDims
 export {
   limitMax,
   half,
+  belowHalf,
   double,
   openTab,
   cacheValues,
@@ -69,20 +69,8 @@ const convertUnits = showValue("_alt_units", "imperial")
 const showAndConvertUnitsFn = field_config => {
   let displayValue = null
 
+  // Porosity fields display the geometric porosity returned by WASM, so they are not calculated here
   switch (field_config.id) {
-    case "porosity":
-      displayValue = (
-        Math.PI * (($id("hole_radius_mm").valueAsNumber / 1000) ** 2) / (($id("repeat_distance_mm").valueAsNumber / 1000) ** 2)
-      ).toFixed(6)
-      $id(field_config.id).innerHTML = displayValue
-      break
-
-    case "slotted_porosity":
-      let sw = $id("slot_width_mm").value / 1000
-      displayValue = (sw / (sw + ($id("slot_distance_mm").valueAsNumber / 1000))).toFixed(6)
-      $id(field_config.id).innerHTML = displayValue
-      break
-
     case "lock_abs_dims":
       if ($id("lock_abs_dims").checked) {
         let air_gap = $id("air_gap_mm")
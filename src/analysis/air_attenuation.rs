@@ -0,0 +1,95 @@
+/***********************************************************************************************************************
+ * Porous Absorber Calculator - Atmospheric absorption
+ *
+ * Reports the ISO 9613-1 attenuation coefficient of air at each requested frequency, together with the energy
+ * attenuation coefficient m used in reverberation time calculations, where the air absorption in a room of volume V
+ * is 4mV
+ *
+ * (c) Chris Whealy 2020, 2024
+ */
+use libm::log10;
+use serde_derive::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+
+use crate::{
+    config::{air::AirConfig, errors_to_js, ok_to_js, ErrorCode, GenericError},
+    trace::{trace_flags::trace_flag_for, *},
+};
+
+pub const MOD_NAME: &str = "analysis::air_attenuation";
+
+/***********************************************************************************************************************
+ * Values received from the client
+ */
+#[derive(Debug, Deserialize)]
+pub struct AirAttenuationArgs {
+    pub air_temp: f64,
+    pub air_pressure: f64,
+    pub relative_humidity: f64,
+    pub frequencies: Vec<f64>,
+}
+
+/***********************************************************************************************************************
+ * Values returned to JavaScript
+ */
+#[derive(Debug, Serialize)]
+pub struct AttenuationPoint {
+    pub freq: f64,
+    // Attenuation coefficient (dB/m)
+    pub attenuation: f64,
+    // Energy attenuation coefficient (1/m)
+    pub energy_attenuation: f64,
+}
+
+/***********************************************************************************************************************
+ * Handle incoming arguments for calculating atmospheric attenuation
+ */
+pub fn prepare(arg_obj: AirAttenuationArgs) -> JsValue {
+    let trace_boundary = make_boundary_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, "prepare");
+    trace_boundary(TraceAction::Enter);
+
+    let mut errors: Vec<GenericError> = vec![];
+
+    let air_config = AirConfig::new(arg_obj.air_temp, arg_obj.air_pressure, arg_obj.relative_humidity).unwrap_or_else(
+        |err: GenericError| {
+            errors.push(err);
+            AirConfig::default()
+        },
+    );
+
+    for (idx, freq) in arg_obj.frequencies.iter().enumerate() {
+        if !(freq.is_finite() && *freq > 0.0) {
+            errors.push(
+                GenericError::new(ErrorCode::InvalidValue, format!("Invalid frequency '{:?}'", freq))
+                    .for_field(&format!("frequencies.{}", idx))
+                    .with_value(*freq),
+            );
+        }
+    }
+
+    let result = if errors.is_empty() {
+        ok_to_js(&attenuation_points(&air_config, &arg_obj.frequencies))
+    } else {
+        errors_to_js(&errors)
+    };
+
+    trace_boundary(TraceAction::Exit);
+    result
+}
+
+pub fn attenuation_points(air_config: &AirConfig, frequencies: &[f64]) -> Vec<AttenuationPoint> {
+    let db_per_neper = 10.0 * log10(std::f64::consts::E);
+
+    frequencies
+        .iter()
+        .map(|freq| {
+            let attenuation = air_config.attenuation_coefficient(*freq);
+
+            AttenuationPoint {
+                freq: *freq,
+                attenuation,
+                energy_attenuation: attenuation / db_per_neper,
+            }
+        })
+        .collect()
+}
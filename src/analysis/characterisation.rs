@@ -0,0 +1,313 @@
+/***********************************************************************************************************************
+ * Porous Absorber Calculator - Inverse characterisation of a porous material
+ *
+ * Given measured absorption data for a porous layer of known thickness mounted with a known air gap, find the flow
+ * resistivity that minimises the RMS difference between the measured values and those predicted by the rigid backed
+ * porous absorber calculation engine
+ *
+ * (c) Chris Whealy 2020, 2024
+ */
+use libm::{exp, log, sqrt};
+use serde_derive::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+
+use crate::{
+    config::{chart::MeasuredPoint, config_set::ConfigSet, errors_to_js, ok_to_js, ErrorCode, GenericError},
+    devices::{
+        porous_absorber::{
+            calc_engine::calculate_plot_point, config::FLOW_RESISTIVITY_RANGE, PorousAbsorberArgs, PorousLayerConfig,
+        },
+        DeviceTypeArgs,
+    },
+    trace::{trace_flags::trace_flag_for, *},
+};
+
+pub const MOD_NAME: &str = "analysis::characterisation";
+
+const MIN_MEASUREMENTS: usize = 3;
+
+// Number of logarithmically spaced flow resistivity values used to bracket the global minimum
+const COARSE_SCAN_STEPS: u32 = 60;
+
+// The golden section search stops once the bracket is narrower than this (in units of ln(sigma))
+const LN_SIGMA_TOLERANCE: f64 = 0.0001;
+
+/***********************************************************************************************************************
+ * Values received from the client
+ *
+ * The flow resistivity in the device arguments is ignored
+ */
+#[derive(Debug, Deserialize)]
+pub struct CharacterisationArgs {
+    pub device: PorousAbsorberArgs,
+    pub measurements: Vec<MeasuredPoint>,
+}
+
+/***********************************************************************************************************************
+ * Best fit and goodness-of-fit metrics to be returned to JavaScript
+ */
+#[derive(Debug, Serialize)]
+pub struct FittedPoint {
+    pub freq: f64,
+    pub measured: f64,
+    pub predicted: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CharacterisationInfo {
+    pub flow_resistivity: u32,
+    pub rms_error: f64,
+    pub max_error: f64,
+    pub r_squared: f64,
+    pub fitted_points: Vec<FittedPoint>,
+}
+
+/***********************************************************************************************************************
+ * Handle incoming arguments for fitting the flow resistivity of a porous layer to measured absorption data
+ */
+pub fn prepare(arg_obj: CharacterisationArgs) -> JsValue {
+    let trace_boundary = make_boundary_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, "prepare");
+    trace_boundary(TraceAction::Enter);
+
+    let mut errors: Vec<GenericError> = vec![];
+
+    if arg_obj.measurements.len() < MIN_MEASUREMENTS {
+        errors.push(
+            GenericError::new(
+                ErrorCode::OutOfRange,
+                format!(
+                    "At least {} measured values are needed, not '{}'",
+                    MIN_MEASUREMENTS,
+                    arg_obj.measurements.len()
+                ),
+            )
+            .for_field("measurements")
+            .with_value(arg_obj.measurements.len() as f64),
+        );
+    }
+
+    for (idx, m) in arg_obj.measurements.iter().enumerate() {
+        if !(m.freq.is_finite() && m.freq > 0.0 && m.abs.is_finite()) {
+            errors.push(
+                GenericError::new(
+                    ErrorCode::InvalidValue,
+                    format!("Invalid measurement of '{:?}' at '{:?}' Hz", m.abs, m.freq),
+                )
+                .for_field(&format!("measurements.{}", idx))
+                .with_value(m.abs),
+            );
+        }
+    }
+
+    // If the measurements and device arguments are all valid, then perform the fit, else return the validation errors
+    let result = match arg_obj.device.config_set() {
+        Ok(mut config_set) if errors.is_empty() => {
+            ok_to_js(&fit_flow_resistivity(&mut config_set, &arg_obj.measurements))
+        },
+        Ok(_) => errors_to_js(&errors),
+        Err(device_errs) => {
+            errors.extend(device_errs.into_iter().map(|err| err.within("device")));
+            errors_to_js(&errors)
+        },
+    };
+
+    trace_boundary(TraceAction::Exit);
+    result
+}
+
+/***********************************************************************************************************************
+ * Find the flow resistivity that best fits the measurements
+ *
+ * The search is performed on ln(sigma) because absorption varies with the order of magnitude of the flow resistivity
+ * far more than with its absolute value.  A coarse scan across the whole valid range first brackets the global minimum,
+ * then a golden section search refines it
+ */
+pub fn fit_flow_resistivity(config_set: &mut ConfigSet, measurements: &[MeasuredPoint]) -> CharacterisationInfo {
+    const FN_NAME: &str = "fit_flow_resistivity";
+    let trace_boundary = make_boundary_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, FN_NAME);
+    let trace = make_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, FN_NAME);
+
+    trace_boundary(TraceAction::Enter);
+
+    let ln_min = log(FLOW_RESISTIVITY_RANGE.min as f64);
+    let ln_max = log(FLOW_RESISTIVITY_RANGE.max as f64);
+    let ln_step = (ln_max - ln_min) / COARSE_SCAN_STEPS as f64;
+
+    let mut rms_at = |ln_sigma: f64| rms_error(config_set, measurements, sigma_from_ln(ln_sigma));
+
+    // Coarse scan
+    let best_step = (0..=COARSE_SCAN_STEPS)
+        .map(|step| (step, rms_at(ln_min + step as f64 * ln_step)))
+        .fold((0, f64::MAX), |best, this| if this.1 < best.1 { this } else { best })
+        .0;
+
+    trace(format!(
+        "Coarse scan minimum at sigma = {}",
+        sigma_from_ln(ln_min + best_step as f64 * ln_step)
+    ));
+
+    // Golden section refinement between the neighbours of the best coarse value
+    let inv_phi = (sqrt(5.0) - 1.0) / 2.0;
+    let mut lo = ln_min + best_step.saturating_sub(1) as f64 * ln_step;
+    let mut hi = (ln_min + (best_step + 1) as f64 * ln_step).min(ln_max);
+    let mut x1 = hi - inv_phi * (hi - lo);
+    let mut x2 = lo + inv_phi * (hi - lo);
+    let mut f1 = rms_at(x1);
+    let mut f2 = rms_at(x2);
+
+    while hi - lo > LN_SIGMA_TOLERANCE {
+        if f1 < f2 {
+            hi = x2;
+            x2 = x1;
+            f2 = f1;
+            x1 = hi - inv_phi * (hi - lo);
+            f1 = rms_at(x1);
+        } else {
+            lo = x1;
+            x1 = x2;
+            f1 = f2;
+            x2 = lo + inv_phi * (hi - lo);
+            f2 = rms_at(x2);
+        }
+    }
+
+    let flow_resistivity = sigma_from_ln((lo + hi) / 2.0);
+    trace(format!("Best fit sigma = {}", flow_resistivity));
+
+    set_flow_resistivity(config_set, flow_resistivity);
+
+    let fitted_points: Vec<FittedPoint> = measurements
+        .iter()
+        .map(|m| FittedPoint {
+            freq: m.freq,
+            measured: m.abs,
+            predicted: predicted_abs(config_set, m.freq),
+        })
+        .collect();
+
+    let residuals: Vec<f64> = fitted_points.iter().map(|fp| fp.measured - fp.predicted).collect();
+    let mean_measured = measurements.iter().map(|m| m.abs).sum::<f64>() / measurements.len() as f64;
+    let ss_residual: f64 = residuals.iter().map(|r| r * r).sum();
+    let ss_total: f64 = measurements
+        .iter()
+        .map(|m| (m.abs - mean_measured) * (m.abs - mean_measured))
+        .sum();
+
+    trace_boundary(TraceAction::Exit);
+
+    CharacterisationInfo {
+        flow_resistivity,
+        rms_error: sqrt(ss_residual / residuals.len() as f64),
+        max_error: residuals.iter().fold(0.0, |acc: f64, r| acc.max(r.abs())),
+        r_squared: if ss_total > 0.0 { 1.0 - ss_residual / ss_total } else { 0.0 },
+        fitted_points,
+    }
+}
+
+//**********************************************************************************************************************
+// Private API
+//**********************************************************************************************************************
+
+fn sigma_from_ln(ln_sigma: f64) -> u32 {
+    (exp(ln_sigma).round() as u32).clamp(FLOW_RESISTIVITY_RANGE.min, FLOW_RESISTIVITY_RANGE.max)
+}
+
+fn set_flow_resistivity(config_set: &mut ConfigSet, sigma: u32) {
+    let thickness_mm = config_set.porous_config.as_ref().unwrap().thickness_mm;
+    config_set.porous_config = PorousLayerConfig::new(thickness_mm, sigma).ok();
+}
+
+/***********************************************************************************************************************
+ * Predicted absorption - a zero air gap is compared against the "no air gap" series
+ */
+fn predicted_abs(config_set: &ConfigSet, frequency: f64) -> f64 {
+    let (abs_no_air_gap, abs_air_gap) = calculate_plot_point(frequency, config_set);
+
+    if config_set.cavity_config.air_gap_mm == 0 {
+        abs_no_air_gap
+    } else {
+        abs_air_gap
+    }
+}
+
+fn rms_error(config_set: &mut ConfigSet, measurements: &[MeasuredPoint], sigma: u32) -> f64 {
+    set_flow_resistivity(config_set, sigma);
+
+    let sum_sq = measurements.iter().fold(0.0, |acc, m| {
+        let diff = m.abs - predicted_abs(config_set, m.freq);
+        acc + diff * diff
+    });
+
+    sqrt(sum_sq / measurements.len() as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn device(air_gap_mm: u16) -> PorousAbsorberArgs {
+        serde_json::from_value(json!({
+            "absorber_thickness_mm": 50,
+            "flow_resistivity": 16500,
+            "air_gap_mm": air_gap_mm,
+            "angle": 0,
+            "graph_start_freq": 62.5,
+            "smooth_curve": false,
+            "subdivision": 3,
+            "show_diagram": false,
+            "air_temp": 20,
+            "air_pressure": 1.0,
+        }))
+        .unwrap()
+    }
+
+    // Absorption predicted by the porous absorber calculation engine for a known flow resistivity
+    fn synthetic_measurements(config_set: &mut ConfigSet, sigma: u32) -> Vec<MeasuredPoint> {
+        set_flow_resistivity(config_set, sigma);
+
+        config_set
+            .chart_config
+            .frequencies
+            .clone()
+            .into_iter()
+            .map(|freq| MeasuredPoint {
+                freq,
+                abs: predicted_abs(config_set, freq),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn recovers_known_flow_resistivity() {
+        for (air_gap_mm, sigma) in [(0, 8000), (100, 12000), (50, 45000)] {
+            let mut config_set = device(air_gap_mm).config_set().unwrap();
+            let measurements = synthetic_measurements(&mut config_set, sigma);
+
+            let fit = fit_flow_resistivity(&mut config_set, &measurements);
+
+            assert!(
+                (fit.flow_resistivity as f64 - sigma as f64).abs() / (sigma as f64) < 0.005,
+                "fitted {} instead of {} with a {}mm air gap",
+                fit.flow_resistivity,
+                sigma,
+                air_gap_mm
+            );
+            assert!(fit.r_squared > 0.9999, "r² = {}", fit.r_squared);
+            assert!(fit.rms_error < 1.0e-3);
+            assert_eq!(fit.fitted_points.len(), measurements.len());
+        }
+    }
+
+    #[test]
+    fn fit_is_limited_to_flow_resistivity_range() {
+        let mut config_set = device(100).config_set().unwrap();
+        let measurements = synthetic_measurements(&mut config_set, FLOW_RESISTIVITY_RANGE.max);
+
+        let fit = fit_flow_resistivity(&mut config_set, &measurements);
+
+        assert!(FLOW_RESISTIVITY_RANGE.contains(fit.flow_resistivity));
+        assert!(fit.flow_resistivity as f64 > 0.99 * FLOW_RESISTIVITY_RANGE.max as f64);
+    }
+}
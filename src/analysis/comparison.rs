@@ -0,0 +1,229 @@
+/***********************************************************************************************************************
+ * Porous Absorber Calculator - Comparison of several designs
+ *
+ * Each design can be any device type.  Every design is calculated at the frequencies of a shared chart, then the
+ * selected series of each design are plotted together so that the designs can be compared directly
+ *
+ * (c) Chris Whealy 2020, 2024
+ */
+use serde_derive::Deserialize;
+use serde_json::Value;
+use wasm_bindgen::JsValue;
+
+use crate::{
+    chart::{
+        constants::{CHART_TITLE_COMPARISON, COMPARISON_COLOURS},
+        render::{self, ComparedDevice},
+    },
+    config::{
+        chart::{ChartArgs, ChartConfig, SeriesMetadata},
+        config_set::ConfigSet,
+        errors_to_js, ok_to_js, ErrorCode, GenericError,
+    },
+    devices::DeviceArgs,
+    trace::{trace_flags::trace_flag_for, *},
+};
+
+pub const MOD_NAME: &str = "analysis::comparison";
+
+// The chart key and device thumbnails become unreadable beyond this many designs
+const MAX_DESIGNS: usize = 6;
+
+/***********************************************************************************************************************
+ * Values received from the client
+ *
+ * The chart settings of the individual designs are replaced by those of the comparison, so they may be omitted from
+ * each design.  If no series indices are given for a design, all of its series are plotted
+ */
+#[derive(Debug, Deserialize)]
+pub struct ComparisonDesign {
+    pub label: String,
+    pub device: Value,
+    #[serde(default)]
+    pub series: Vec<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ComparisonArgs {
+    pub designs: Vec<ComparisonDesign>,
+    #[serde(flatten)]
+    pub chart: ChartArgs,
+}
+
+/***********************************************************************************************************************
+ * Handle incoming arguments for comparing several designs
+ */
+pub fn prepare(arg_obj: ComparisonArgs) -> JsValue {
+    let trace_boundary = make_boundary_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, "prepare");
+    trace_boundary(TraceAction::Enter);
+
+    let mut errors: Vec<GenericError> = vec![];
+
+    if arg_obj.designs.is_empty() || arg_obj.designs.len() > MAX_DESIGNS {
+        errors.push(
+            GenericError::new(
+                ErrorCode::OutOfRange,
+                format!(
+                    "A comparison must contain between 1 and {} designs, not '{}'",
+                    MAX_DESIGNS,
+                    arg_obj.designs.len()
+                ),
+            )
+            .for_field("designs")
+            .with_value(arg_obj.designs.len() as f64)
+            .with_limits(1.0, MAX_DESIGNS as f64),
+        );
+    }
+
+    // Measured data cannot be overlaid on a comparison
+    let chart_args = ChartArgs {
+        measured_data: vec![],
+        ..arg_obj.chart.clone()
+    };
+
+    let chart_config = chart_args.chart_config().unwrap_or_else(|err: GenericError| {
+        errors.push(err);
+        ChartConfig::default()
+    });
+
+    // Error messages from each design are prefixed with the design's label
+    let designs: Vec<(&ComparisonDesign, DeviceArgs, ConfigSet)> = arg_obj
+        .designs
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, design)| {
+            let device_errs = match design_device(design, &chart_args) {
+                Ok(device) => match device.config_set_for_chart(Ok(chart_config.clone())) {
+                    Ok(config_set) => return Some((design, device, config_set)),
+                    Err(device_errs) => device_errs,
+                },
+                Err(err) => vec![err],
+            };
+
+            errors.extend(
+                device_errs
+                    .into_iter()
+                    .map(|err| err.within(&format!("designs.{}.device", idx)).in_context(&design.label)),
+            );
+            None
+        })
+        .collect();
+
+    let result = if errors.is_empty() {
+        compare(&designs, &chart_config)
+    } else {
+        errors_to_js(&errors)
+    };
+
+    trace_boundary(TraceAction::Exit);
+    result
+}
+
+//**********************************************************************************************************************
+// Private API
+//**********************************************************************************************************************
+
+/***********************************************************************************************************************
+ * Calculate each design, then plot the selected series
+ */
+fn compare(designs: &[(&ComparisonDesign, DeviceArgs, ConfigSet)], chart_config: &ChartConfig) -> JsValue {
+    const FN_NAME: &str = "compare";
+    let trace_boundary = make_boundary_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, FN_NAME);
+    let trace = make_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, FN_NAME);
+
+    trace_boundary(TraceAction::Enter);
+
+    let mut errors: Vec<GenericError> = vec![];
+
+    let devices: Vec<ComparedDevice> = designs
+        .iter()
+        .enumerate()
+        .map(|(idx, (design, device, config_set))| {
+            let device_info = device.calculate(config_set);
+            let series_count = device_info.abs_series.len();
+
+            for series_idx in design.series.iter().filter(|idx| **idx >= series_count) {
+                errors.push(
+                    GenericError::new(
+                        ErrorCode::OutOfRange,
+                        format!("series index must be less than {}, not '{}'", series_count, series_idx),
+                    )
+                    .for_field(&format!("designs.{}.series", idx))
+                    .with_value(*series_idx as f64)
+                    .with_limits(0.0, (series_count - 1) as f64)
+                    .in_context(&design.label),
+                );
+            }
+
+            ComparedDevice {
+                label: &design.label,
+                device_info,
+                series: if design.series.is_empty() {
+                    (0..series_count).collect()
+                } else {
+                    design.series.clone()
+                },
+            }
+        })
+        .collect();
+
+    let result = if errors.is_empty() {
+        // Each key entry names both the design and the series
+        let series_names: Vec<String> = devices
+            .iter()
+            .flat_map(|device| {
+                device
+                    .series
+                    .iter()
+                    .map(move |idx| format!("{} - {}", device.label, device.device_info.abs_series[*idx].name))
+            })
+            .collect();
+
+        trace(format!("Plotting {} series", series_names.len()));
+
+        let series_metadata: Vec<SeriesMetadata> = series_names
+            .iter()
+            .enumerate()
+            .map(|(idx, name)| SeriesMetadata {
+                name,
+                plot_colour: COMPARISON_COLOURS[idx % COMPARISON_COLOURS.len()],
+            })
+            .collect();
+
+        let mut chart_info = render::comparison(&devices, &series_metadata, chart_config, CHART_TITLE_COMPARISON);
+
+        // Warnings are identified in the same way as errors
+        chart_info.warnings = designs
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, (design, device, config_set))| {
+                device
+                    .warnings(config_set)
+                    .into_iter()
+                    .map(move |warning| warning.within(&format!("designs.{}.device", idx)).in_context(&design.label))
+            })
+            .collect();
+
+        ok_to_js(&chart_info)
+    } else {
+        errors_to_js(&errors)
+    };
+
+    trace_boundary(TraceAction::Exit);
+    result
+}
+
+/***********************************************************************************************************************
+ * The chart settings of the comparison are added to the device arguments of each design
+ */
+fn design_device(design: &ComparisonDesign, chart_args: &ChartArgs) -> Result<DeviceArgs, GenericError> {
+    let mut device = design.device.clone();
+
+    if let (Value::Object(device_fields), Ok(Value::Object(chart_fields))) =
+        (&mut device, serde_json::to_value(chart_args))
+    {
+        device_fields.extend(chart_fields);
+    }
+
+    serde_json::from_value::<DeviceArgs>(device).map_err(|err| GenericError::new(ErrorCode::Malformed, err.to_string()))
+}
@@ -0,0 +1,288 @@
+/***********************************************************************************************************************
+ * Porous Absorber Calculator - Impedance tube data processing
+ *
+ * Derives the normal incidence reflection coefficient, surface impedance and absorption coefficient of a sample from
+ * the transfer function H12 measured in a two-microphone impedance tube, as described in ISO 10534-2
+ *
+ * Microphone 1 is the microphone furthest from the sample, and H12 is the ratio of the complex sound pressure at
+ * microphone 2 to that at microphone 1
+ *
+ * (c) Chris Whealy 2020, 2024
+ */
+use num::complex::Complex;
+use serde_derive::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+
+use crate::{
+    config::{air::AirConfig, constants, errors_to_js, ok_to_js, ranges::NamedRange, units, ErrorCode, GenericError},
+    trace::{trace_flags::trace_flag_for, *},
+};
+
+pub const MOD_NAME: &str = "analysis::impedance_tube";
+
+const MIN_MEASUREMENTS: usize = 1;
+
+// Working frequency range limits from ISO 10534-2 section 4.2 for a tube of circular cross section
+const UPPER_LIMIT_DIAMETER_FACTOR: f64 = 0.58;
+const UPPER_LIMIT_SPACING_FACTOR: f64 = 0.45;
+const LOWER_LIMIT_SPACING_FACTOR: f64 = 0.05;
+
+/***********************************************************************************************************************
+ * Impedance tube range check values
+ */
+const MIC_SPACING_RANGE: NamedRange<f64> = NamedRange {
+    name: constants::TXT_MIC_SPACING,
+    field: "mic_spacing_mm",
+    units: constants::UNITS_DISTANCE,
+    min: 5.0,
+    default: 50.0,
+    max: 500.0,
+};
+
+const SAMPLE_DISTANCE_RANGE: NamedRange<f64> = NamedRange {
+    name: constants::TXT_SAMPLE_DISTANCE,
+    field: "sample_distance_mm",
+    units: constants::UNITS_DISTANCE,
+    min: 10.0,
+    default: 100.0,
+    max: 1000.0,
+};
+
+const TUBE_DIAMETER_RANGE: NamedRange<f64> = NamedRange {
+    name: constants::TXT_TUBE_DIAMETER,
+    field: "tube_diameter_mm",
+    units: constants::UNITS_DISTANCE,
+    min: 10.0,
+    default: 100.0,
+    max: 500.0,
+};
+
+/***********************************************************************************************************************
+ * Values received from the client
+ *
+ * The sample distance is measured from the front face of the sample to microphone 1
+ */
+#[derive(Debug, Deserialize)]
+pub struct TransferFunctionPoint {
+    pub freq: f64,
+    pub h12_re: f64,
+    pub h12_im: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImpedanceTubeArgs {
+    pub mic_spacing_mm: f64,
+    pub sample_distance_mm: f64,
+    pub tube_diameter_mm: f64,
+    pub air_temp: f64,
+    pub air_pressure: f64,
+    #[serde(default)]
+    pub relative_humidity: f64,
+    pub transfer_function: Vec<TransferFunctionPoint>,
+}
+
+/***********************************************************************************************************************
+ * Impedance tube geometry
+ */
+#[derive(Debug)]
+pub struct TubeConfig {
+    pub mic_spacing: f64,
+    pub sample_distance: f64,
+    pub tube_diameter: f64,
+}
+
+impl TubeConfig {
+    pub fn default() -> TubeConfig {
+        TubeConfig::new(
+            MIC_SPACING_RANGE.default,
+            SAMPLE_DISTANCE_RANGE.default,
+            TUBE_DIAMETER_RANGE.default,
+        )
+        .unwrap()
+    }
+
+    pub fn new(
+        mic_spacing_arg: f64,
+        sample_distance_arg: f64,
+        tube_diameter_arg: f64,
+    ) -> Result<TubeConfig, GenericError> {
+        if !MIC_SPACING_RANGE.contains(mic_spacing_arg) {
+            return Err(GenericError::new_from_f64(MIC_SPACING_RANGE, mic_spacing_arg));
+        }
+
+        if !SAMPLE_DISTANCE_RANGE.contains(sample_distance_arg) {
+            return Err(GenericError::new_from_f64(SAMPLE_DISTANCE_RANGE, sample_distance_arg));
+        }
+
+        if !TUBE_DIAMETER_RANGE.contains(tube_diameter_arg) {
+            return Err(GenericError::new_from_f64(TUBE_DIAMETER_RANGE, tube_diameter_arg));
+        }
+
+        // Microphone 2 must lie between microphone 1 and the sample
+        if sample_distance_arg <= mic_spacing_arg {
+            return Err(GenericError::new(
+                ErrorCode::Inconsistent,
+                format!(
+                    "{} {} must be greater than {} {}",
+                    SAMPLE_DISTANCE_RANGE.name,
+                    units::quote(SAMPLE_DISTANCE_RANGE.units, sample_distance_arg),
+                    MIC_SPACING_RANGE.name,
+                    units::quote(MIC_SPACING_RANGE.units, mic_spacing_arg)
+                ),
+            )
+            .for_field(SAMPLE_DISTANCE_RANGE.field)
+            .with_value(sample_distance_arg)
+            .with_limits(mic_spacing_arg, SAMPLE_DISTANCE_RANGE.max)
+            .in_units(SAMPLE_DISTANCE_RANGE.units));
+        }
+
+        Ok(TubeConfig {
+            mic_spacing: mic_spacing_arg / 1000.0,
+            sample_distance: sample_distance_arg / 1000.0,
+            tube_diameter: tube_diameter_arg / 1000.0,
+        })
+    }
+}
+
+/***********************************************************************************************************************
+ * Processed values to be returned to JavaScript
+ *
+ * The surface impedance is normalised to the characteristic impedance of air.  Since each point carries "freq" and
+ * "abs" properties, the list of points can be passed back unchanged as measured data for overlay or characterisation
+ */
+#[derive(Debug, Serialize)]
+pub struct TubePoint {
+    pub freq: f64,
+    pub abs: f64,
+    pub reflection_re: f64,
+    pub reflection_im: f64,
+    pub impedance_re: f64,
+    pub impedance_im: f64,
+    pub in_working_range: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImpedanceTubeInfo {
+    pub lower_freq_limit: f64,
+    pub upper_freq_limit: f64,
+    pub points: Vec<TubePoint>,
+}
+
+/***********************************************************************************************************************
+ * Handle incoming arguments for processing impedance tube measurements
+ */
+pub fn prepare(arg_obj: ImpedanceTubeArgs) -> JsValue {
+    let trace_boundary = make_boundary_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, "prepare");
+    trace_boundary(TraceAction::Enter);
+
+    let mut errors: Vec<GenericError> = vec![];
+
+    let tube_config = TubeConfig::new(arg_obj.mic_spacing_mm, arg_obj.sample_distance_mm, arg_obj.tube_diameter_mm)
+        .unwrap_or_else(|err: GenericError| {
+            errors.push(err);
+            TubeConfig::default()
+        });
+
+    let air_config = AirConfig::new(arg_obj.air_temp, arg_obj.air_pressure, arg_obj.relative_humidity).unwrap_or_else(
+        |err: GenericError| {
+            errors.push(err);
+            AirConfig::default()
+        },
+    );
+
+    if arg_obj.transfer_function.len() < MIN_MEASUREMENTS {
+        errors.push(
+            GenericError::new(
+                ErrorCode::InvalidValue,
+                String::from("No transfer function values have been supplied"),
+            )
+            .for_field("transfer_function"),
+        );
+    }
+
+    for (idx, tf) in arg_obj.transfer_function.iter().enumerate() {
+        if !(tf.freq.is_finite() && tf.freq > 0.0 && tf.h12_re.is_finite() && tf.h12_im.is_finite()) {
+            errors.push(
+                GenericError::new(
+                    ErrorCode::InvalidValue,
+                    format!(
+                        "Invalid transfer function value of '{:?} + {:?}i' at '{:?}' Hz",
+                        tf.h12_re, tf.h12_im, tf.freq
+                    ),
+                )
+                .for_field(&format!("transfer_function.{}", idx)),
+            );
+        }
+    }
+
+    // If there are no errors, then process the transfer function, else return the validation errors
+    let result = if errors.is_empty() {
+        ok_to_js(&process(&tube_config, &air_config, &arg_obj.transfer_function))
+    } else {
+        errors_to_js(&errors)
+    };
+
+    trace_boundary(TraceAction::Exit);
+    result
+}
+
+/***********************************************************************************************************************
+ * Convert each transfer function value into the sample's reflection coefficient, surface impedance and absorption
+ *
+ * The same air properties are used as for the calculation engines, so the results can be compared directly with the
+ * predicted absorption at normal incidence
+ */
+pub fn process(
+    tube_config: &TubeConfig,
+    air_config: &AirConfig,
+    transfer_function: &[TransferFunctionPoint],
+) -> ImpedanceTubeInfo {
+    const FN_NAME: &str = "process";
+    let trace_boundary = make_boundary_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, FN_NAME);
+    let trace = make_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, FN_NAME);
+
+    trace_boundary(TraceAction::Enter);
+
+    let lower_freq_limit = LOWER_LIMIT_SPACING_FACTOR * air_config.velocity / tube_config.mic_spacing;
+    let upper_freq_limit = (UPPER_LIMIT_DIAMETER_FACTOR * air_config.velocity / tube_config.tube_diameter)
+        .min(UPPER_LIMIT_SPACING_FACTOR * air_config.velocity / tube_config.mic_spacing);
+
+    trace(format!(
+        "Working frequency range {:.1} Hz to {:.1} Hz",
+        lower_freq_limit, upper_freq_limit
+    ));
+
+    let points = transfer_function
+        .iter()
+        .map(|tf| {
+            let h12 = Complex::new(tf.h12_re, tf.h12_im);
+            let k0 = air_config.tau_over_c * tf.freq;
+
+            // Transfer functions of the incident and reflected waves alone
+            let h_i = Complex::new(0.0, -k0 * tube_config.mic_spacing).exp();
+            let h_r = Complex::new(0.0, k0 * tube_config.mic_spacing).exp();
+
+            let reflection =
+                (h12 - h_i) / (h_r - h12) * Complex::new(0.0, 2.0 * k0 * tube_config.sample_distance).exp();
+            let impedance = (1.0 + reflection) / (1.0 - reflection);
+
+            TubePoint {
+                freq: tf.freq,
+                abs: 1.0 - reflection.norm_sqr(),
+                reflection_re: reflection.re,
+                reflection_im: reflection.im,
+                impedance_re: impedance.re,
+                impedance_im: impedance.im,
+                in_working_range: tf.freq >= lower_freq_limit && tf.freq <= upper_freq_limit,
+            }
+        })
+        .collect();
+
+    trace_boundary(TraceAction::Exit);
+
+    ImpedanceTubeInfo {
+        lower_freq_limit,
+        upper_freq_limit,
+        points,
+    }
+}
@@ -0,0 +1,6 @@
+pub mod air_attenuation;
+pub mod characterisation;
+pub mod comparison;
+pub mod impedance_tube;
+pub mod sensitivity;
+pub mod uncertainty;
@@ -0,0 +1,350 @@
+/***********************************************************************************************************************
+ * Porous Absorber Calculator - Local sensitivity analysis
+ *
+ * For each numeric device argument p, the partial derivative ∂α/∂p is estimated at every frequency using central
+ * finite differences.  The normalised sensitivity p·∂α/∂p is the linearised change in absorption caused by a 100%
+ * change in the argument, which makes arguments with different units directly comparable.  Temperatures are normalised
+ * in kelvin, since a value measured from an arbitrary zero would make the sensitivity vanish at 0°C and change sign
+ * below it.
+ *
+ * Each series then ranks the arguments by the RMS of their normalised sensitivity across all frequencies
+ *
+ * (c) Chris Whealy 2020, 2024
+ */
+use libm::sqrt;
+use serde_derive::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+
+use crate::{
+    config::{
+        air::KELVIN_OFFSET,
+        config_set::ConfigSet,
+        constants, errors_to_js, ok_to_js,
+        ranges::NamedRange,
+        units::{self, Quantity},
+        GenericError,
+    },
+    devices::DeviceTypeArgs,
+    trace::{trace_flags::trace_flag_for, *},
+};
+
+pub const MOD_NAME: &str = "analysis::sensitivity";
+
+/***********************************************************************************************************************
+ * Range check values
+ */
+const STEP_RANGE: NamedRange<f64> = NamedRange {
+    name: constants::TXT_STEP_SIZE,
+    field: "step_pct",
+    units: constants::UNITS_PERCENT,
+    min: 0.01,
+    default: 1.0,
+    max: 10.0,
+};
+
+fn default_step_pct() -> f64 {
+    STEP_RANGE.default
+}
+
+/***********************************************************************************************************************
+ * Values received from the client
+ */
+#[derive(Debug, Deserialize)]
+pub struct SensitivityArgs<T> {
+    pub device: T,
+    #[serde(default = "default_step_pct")]
+    pub step_pct: f64,
+}
+
+/***********************************************************************************************************************
+ * Sensitivity information to be returned to JavaScript
+ */
+#[derive(Debug, Serialize)]
+pub struct SensitivityPoint {
+    pub freq: f64,
+    pub abs: f64,
+    pub derivative: f64,
+    pub normalised: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FieldSensitivity {
+    pub field: &'static str,
+    pub value: f64,
+    pub units: &'static str,
+    pub rms_normalised: f64,
+    pub sensitivity_points: Vec<SensitivityPoint>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SeriesSensitivity<'a> {
+    pub name: &'a str,
+    pub ranking: Vec<&'static str>,
+    pub fields: Vec<FieldSensitivity>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SensitivityInfo<'a> {
+    pub series: Vec<SeriesSensitivity<'a>>,
+    pub skipped_fields: Vec<&'static str>,
+}
+
+/***********************************************************************************************************************
+ * Handle incoming arguments for a sensitivity analysis of any device type
+ */
+pub fn prepare<T: DeviceTypeArgs>(arg_obj: SensitivityArgs<T>) -> JsValue {
+    let trace_boundary = make_boundary_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, "prepare");
+    trace_boundary(TraceAction::Enter);
+
+    let mut errors: Vec<GenericError> = vec![];
+
+    if !STEP_RANGE.contains(arg_obj.step_pct) {
+        errors.push(GenericError::new_from_f64(STEP_RANGE, arg_obj.step_pct));
+    }
+
+    // If the analysis and device arguments are all valid, then run the analysis, else return the validation errors
+    let result = match arg_obj.device.config_set() {
+        Ok(config_set) if errors.is_empty() => ok_to_js(&analyse(&arg_obj.device, &config_set, arg_obj.step_pct)),
+        Ok(_) => errors_to_js(&errors),
+        Err(device_errs) => {
+            errors.extend(device_errs.into_iter().map(|err| err.within("device")));
+            errors_to_js(&errors)
+        },
+    };
+
+    trace_boundary(TraceAction::Exit);
+    result
+}
+
+/***********************************************************************************************************************
+ * Calculate the sensitivity of every series to every numeric argument
+ */
+pub fn analyse<'a, T: DeviceTypeArgs>(device: &T, config_set: &'a ConfigSet, step_pct: f64) -> SensitivityInfo<'a> {
+    const FN_NAME: &str = "analyse";
+    let trace_boundary = make_boundary_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, FN_NAME);
+    let trace = make_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, FN_NAME);
+
+    trace_boundary(TraceAction::Enter);
+
+    let nominal_info = T::calculate(config_set);
+    let mut skipped_fields: Vec<&'static str> = vec![];
+
+    let mut series: Vec<SeriesSensitivity> = nominal_info
+        .abs_series
+        .iter()
+        .map(|s| SeriesSensitivity {
+            name: s.name,
+            ranking: vec![],
+            fields: vec![],
+        })
+        .collect();
+
+    for (field, value) in device.numeric_fields() {
+        match central_difference(device, field, value, step_pct) {
+            Some((abs_plus, abs_minus, delta)) => {
+                trace(format!("{} = {}, difference step = {}", field, value, delta));
+
+                // The argument value and derivatives are quoted in the units of the client's request, but the
+                // normalised sensitivity is dimensionless
+                let (display_value, metric_per_unit) = match Quantity::of_field(field) {
+                    Some(quantity) => {
+                        let request_units = units::request_units();
+                        (
+                            request_units.metric_to_units(quantity, value),
+                            request_units.interval_to_metric(quantity, 1.0),
+                        )
+                    },
+                    None => (value, 1.0),
+                };
+
+                let normalising_value = match Quantity::of_field(field) {
+                    Some(Quantity::Temperature) => value + KELVIN_OFFSET,
+                    _ => value,
+                };
+
+                for (series_idx, nominal_series) in nominal_info.abs_series.iter().enumerate() {
+                    let sensitivity_points: Vec<SensitivityPoint> = nominal_series
+                        .plot_points
+                        .iter()
+                        .enumerate()
+                        .map(|(freq_idx, plot_point)| {
+                            let derivative = (abs_plus[series_idx][freq_idx] - abs_minus[series_idx][freq_idx]) / delta;
+
+                            SensitivityPoint {
+                                freq: plot_point.freq,
+                                abs: plot_point.abs,
+                                derivative: derivative * metric_per_unit,
+                                normalised: normalising_value * derivative,
+                            }
+                        })
+                        .collect();
+
+                    let rms_normalised = sqrt(
+                        sensitivity_points.iter().map(|sp| sp.normalised * sp.normalised).sum::<f64>()
+                            / sensitivity_points.len() as f64,
+                    );
+
+                    series[series_idx].fields.push(FieldSensitivity {
+                        field,
+                        value: display_value,
+                        units: units::field_units(field),
+                        rms_normalised,
+                        sensitivity_points,
+                    });
+                }
+            },
+
+            None => {
+                trace(format!("Skipping {} - no valid neighbouring value", field));
+                skipped_fields.push(field);
+            },
+        }
+    }
+
+    // Rank the arguments of each series from most to least influential
+    for s in series.iter_mut() {
+        s.fields.sort_by(|a, b| b.rms_normalised.total_cmp(&a.rms_normalised));
+        s.ranking = s.fields.iter().map(|f| f.field).collect();
+    }
+
+    trace_boundary(TraceAction::Exit);
+    SensitivityInfo { series, skipped_fields }
+}
+
+//**********************************************************************************************************************
+// Private API
+//**********************************************************************************************************************
+
+// Absorption values indexed by series, then frequency
+type AbsGrid = Vec<Vec<f64>>;
+
+/***********************************************************************************************************************
+ * Calculate the device either side of the nominal value of one argument
+ *
+ * Integer arguments are rounded when set, so the step actually taken is read back from the perturbed arguments.  If
+ * the relative step is too small to change an integer argument, a step of one unit is used instead.  If one neighbour
+ * falls outside the argument's valid range, a one-sided difference is used
+ */
+fn central_difference<T: DeviceTypeArgs>(
+    device: &T,
+    field: &str,
+    value: f64,
+    step_pct: f64,
+) -> Option<(AbsGrid, AbsGrid, f64)> {
+    let step = value.abs() * step_pct / 100.0;
+
+    let nominal = || abs_grid(device).map(|grid| (value, grid));
+
+    let (plus_value, abs_plus) = neighbour(device, field, value, step).or_else(nominal)?;
+    let (minus_value, abs_minus) = neighbour(device, field, value, -step).or_else(nominal)?;
+
+    let delta = plus_value - minus_value;
+
+    if delta == 0.0 {
+        None
+    } else {
+        Some((abs_plus, abs_minus, delta))
+    }
+}
+
+fn neighbour<T: DeviceTypeArgs>(device: &T, field: &str, value: f64, step: f64) -> Option<(f64, AbsGrid)> {
+    let mut perturbed = device.clone();
+    perturbed.set_numeric_field(field, value + step);
+
+    // Fall back to a whole unit step if rounding has swallowed the requested step
+    if perturbed.numeric_field(field) == Some(value) {
+        perturbed.set_numeric_field(field, value + step.signum());
+    }
+
+    let actual_value = perturbed.numeric_field(field)?;
+
+    if actual_value == value {
+        None
+    } else {
+        abs_grid(&perturbed).map(|grid| (actual_value, grid))
+    }
+}
+
+fn abs_grid<T: DeviceTypeArgs>(device: &T) -> Option<AbsGrid> {
+    let config_set = device.config_set().ok()?;
+
+    Some(
+        T::calculate(&config_set)
+            .abs_series
+            .iter()
+            .map(|series| series.plot_points.iter().map(|pp| pp.abs).collect())
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::devices::porous_absorber::PorousAbsorberArgs;
+
+    fn device() -> PorousAbsorberArgs {
+        serde_json::from_value(json!({
+            "absorber_thickness_mm": 30,
+            "flow_resistivity": 16500,
+            "air_gap_mm": 100,
+            "angle": 0,
+            "graph_start_freq": 62.5,
+            "smooth_curve": false,
+            "subdivision": 3,
+            "show_diagram": false,
+            "air_temp": 20,
+            "air_pressure": 1.0,
+        }))
+        .unwrap()
+    }
+
+    fn rank(ranking: &[&str], field: &str) -> usize {
+        ranking.iter().position(|f| *f == field).unwrap()
+    }
+
+    #[test]
+    fn thickness_outranks_angle_at_normal_incidence() {
+        let device = device();
+        let config_set = device.config_set().unwrap();
+        let info = analyse(&device, &config_set, STEP_RANGE.default);
+
+        assert!(!info.series.is_empty());
+
+        for series in info.series {
+            assert_eq!(series.ranking.len(), series.fields.len());
+            assert!(rank(&series.ranking, "absorber_thickness_mm") < rank(&series.ranking, "angle"));
+            assert!(rank(&series.ranking, "flow_resistivity") < rank(&series.ranking, "angle"));
+
+            // The ranking is ordered by decreasing RMS normalised sensitivity
+            assert!(series
+                .fields
+                .windows(2)
+                .all(|pair| pair[0].rms_normalised >= pair[1].rms_normalised));
+        }
+    }
+
+    #[test]
+    fn central_difference_matches_two_sided_step() {
+        let (abs_plus, abs_minus, delta) = central_difference(&device(), "air_pressure", 1.0, 2.0).unwrap();
+
+        assert!((delta - 0.04).abs() < 1.0e-12);
+        assert_eq!(abs_plus.len(), abs_minus.len());
+    }
+
+    #[test]
+    fn integer_field_falls_back_to_whole_unit_step() {
+        // A 0.1% step of 100mm would be rounded away, so a step of 1mm is taken either side
+        let (_, _, delta) = central_difference(&device(), "air_gap_mm", 100.0, 0.1).unwrap();
+        assert_eq!(delta, 2.0);
+
+        // A 1% step of 30mm is rounded to 0mm, so again falls back to 1mm
+        let (_, _, delta) = central_difference(&device(), "absorber_thickness_mm", 30.0, 1.0).unwrap();
+        assert_eq!(delta, 2.0);
+
+        // An angle of 0 cannot be reduced, so a one-sided difference is used
+        let (_, _, delta) = central_difference(&device(), "angle", 0.0, 1.0).unwrap();
+        assert_eq!(delta, 1.0);
+    }
+}
@@ -0,0 +1,506 @@
+/***********************************************************************************************************************
+ * Porous Absorber Calculator - Monte Carlo uncertainty analysis
+ *
+ * Real-world hole diameters, panel thicknesses and flow resistivities vary from batch to batch.  Each device argument
+ * can be given a tolerance, then the device is recalculated for a set of randomly perturbed arguments.  The spread of
+ * the resulting absorption values is returned as the mean and a pair of percentile bands around the nominal curve.
+ *
+ * Integer arguments are rounded when set, so a tolerance smaller than one whole unit of such an argument is refused.
+ * Samples that fall outside the permitted range of an argument are rejected, and if more than a small fraction of them
+ * are rejected, a warning is returned because the bands no longer represent the whole of the requested distribution.
+ *
+ * (c) Chris Whealy 2020, 2024
+ */
+use serde::Serialize;
+use serde_derive::Deserialize;
+use wasm_bindgen::JsValue;
+
+use crate::{
+    config::{
+        chart::{BandPoint, ChartInfo, SeriesBand},
+        config_set::ConfigSet,
+        constants,
+        errors_to_js, ok_to_js,
+        ranges::NamedRange,
+        units::{self, Quantity, UnitSystem},
+        ErrorCode, GenericError,
+    },
+    devices::DeviceTypeArgs,
+    trace::{trace_flags::trace_flag_for, *},
+    utils::random::Prng,
+};
+
+pub const MOD_NAME: &str = "analysis::uncertainty";
+
+/***********************************************************************************************************************
+ * Range check values
+ */
+const SAMPLE_COUNT_RANGE: NamedRange<u16> = NamedRange {
+    name: constants::TXT_SAMPLE_COUNT,
+    field: "sample_count",
+    units: constants::UNITS_SAMPLES,
+    min: 10,
+    default: 200,
+    max: 5000,
+};
+
+const PERCENTILE_RANGE: NamedRange<f64> = NamedRange {
+    name: constants::TXT_PERCENTILE,
+    field: "lower_percentile",
+    units: constants::UNITS_PERCENT,
+    min: 0.0,
+    default: 5.0,
+    max: 100.0,
+};
+
+// Warn when more than this fraction of the samples are rejected
+const REJECTED_SAMPLES_WARNING: f64 = 0.01;
+
+fn default_sample_count() -> u16 {
+    SAMPLE_COUNT_RANGE.default
+}
+fn default_lower_percentile() -> f64 {
+    PERCENTILE_RANGE.default
+}
+fn default_upper_percentile() -> f64 {
+    PERCENTILE_RANGE.max - PERCENTILE_RANGE.default
+}
+
+/***********************************************************************************************************************
+ * Values received from the client
+ *
+ * For a uniform distribution, the spread is the half-width of the tolerance band.  For a normal distribution, the
+ * spread is the standard deviation.  If the tolerance is relative, the spread is a percentage of the nominal value,
+ * otherwise it is expressed in the units of the argument itself
+ */
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Distribution {
+    Uniform,
+    Normal,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Tolerance {
+    pub field: String,
+    pub distribution: Distribution,
+    pub spread: f64,
+    #[serde(default)]
+    pub relative: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UncertaintyArgs<T> {
+    pub device: T,
+    pub tolerances: Vec<Tolerance>,
+    #[serde(default = "default_sample_count")]
+    pub sample_count: u16,
+    #[serde(default)]
+    pub seed: u32,
+    #[serde(default = "default_lower_percentile")]
+    pub lower_percentile: f64,
+    #[serde(default = "default_upper_percentile")]
+    pub upper_percentile: f64,
+}
+
+/***********************************************************************************************************************
+ * Chart information plus the uncertainty bands to be returned to JavaScript
+ */
+#[derive(Debug, Serialize)]
+pub struct UncertaintyInfo<'a> {
+    #[serde(flatten)]
+    pub chart_info: ChartInfo<'a>,
+    pub bands: Vec<SeriesBand<'a>>,
+    pub valid_samples: u16,
+    pub rejected_samples: u16,
+}
+
+/***********************************************************************************************************************
+ * Handle incoming arguments for a Monte Carlo uncertainty analysis of any device type
+ */
+pub fn prepare<T: DeviceTypeArgs>(arg_obj: UncertaintyArgs<T>) -> JsValue {
+    let trace_boundary = make_boundary_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, "prepare");
+    trace_boundary(TraceAction::Enter);
+
+    let mut errors = validate(&arg_obj);
+
+    // If the analysis and device arguments are all valid, then run the analysis, else return the validation errors
+    let result = match arg_obj.device.config_set() {
+        Ok(config_set) if errors.is_empty() => analyse(&arg_obj, &config_set),
+        Ok(_) => errors_to_js(&errors),
+        Err(device_errs) => {
+            errors.extend(device_errs.into_iter().map(|err| err.within("device")));
+            errors_to_js(&errors)
+        },
+    };
+
+    trace_boundary(TraceAction::Exit);
+    result
+}
+
+//**********************************************************************************************************************
+// Private API
+//**********************************************************************************************************************
+
+/***********************************************************************************************************************
+ * Calculate the nominal device, then each of the randomly perturbed samples
+ */
+fn analyse<T: DeviceTypeArgs>(arg_obj: &UncertaintyArgs<T>, config_set: &ConfigSet) -> JsValue {
+    const FN_NAME: &str = "analyse";
+    let trace_boundary = make_boundary_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, FN_NAME);
+    let trace = make_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, FN_NAME);
+
+    trace_boundary(TraceAction::Enter);
+
+    let nominal_info = T::calculate(config_set);
+
+    // Collect the absorption values of every valid sample, indexed by series, then frequency
+    let mut rng = Prng::new(arg_obj.seed as u64);
+    let mut samples: Vec<Vec<Vec<f64>>> = nominal_info
+        .abs_series
+        .iter()
+        .map(|series| vec![Vec::with_capacity(arg_obj.sample_count as usize); series.plot_points.len()])
+        .collect();
+    let mut rejected_samples: u16 = 0;
+
+    for _ in 0..arg_obj.sample_count {
+        match perturb(&arg_obj.device, &arg_obj.tolerances, &mut rng).config_set() {
+            Ok(sample_config_set) => {
+                let sample_info = T::calculate(&sample_config_set);
+
+                for (series_idx, series) in sample_info.abs_series.iter().enumerate() {
+                    for (freq_idx, plot_point) in series.plot_points.iter().enumerate() {
+                        samples[series_idx][freq_idx].push(plot_point.abs);
+                    }
+                }
+            },
+
+            // A perturbed value can fall outside the permitted range of its argument
+            Err(_) => rejected_samples += 1,
+        }
+    }
+
+    let valid_samples = arg_obj.sample_count - rejected_samples;
+    trace(format!("{} valid samples, {} rejected", valid_samples, rejected_samples));
+
+    let result = if valid_samples == 0 {
+        errors_to_js(&[GenericError::new(
+            ErrorCode::InvalidValue,
+            format!(
+                "None of the {} Monte Carlo samples produced a valid device - reduce the tolerances",
+                arg_obj.sample_count
+            ),
+        )
+        .for_field("tolerances")])
+    } else {
+        let bands: Vec<SeriesBand> = nominal_info
+            .abs_series
+            .iter()
+            .zip(samples.iter_mut())
+            .map(|(series, series_samples)| SeriesBand {
+                name: series.name,
+                band_points: series
+                    .plot_points
+                    .iter()
+                    .zip(series_samples.iter_mut())
+                    .map(|(plot_point, values)| {
+                        values.sort_by(|a, b| a.total_cmp(b));
+
+                        BandPoint {
+                            freq: plot_point.freq,
+                            nominal: plot_point.abs,
+                            mean: values.iter().sum::<f64>() / values.len() as f64,
+                            lower: percentile(values, arg_obj.lower_percentile),
+                            upper: percentile(values, arg_obj.upper_percentile),
+                        }
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        // Plot the nominal curves surrounded by their uncertainty envelopes
+        let mut chart_info = crate::chart::render::generic_device_with_bands(
+            nominal_info,
+            &bands,
+            &config_set.chart_config,
+            &T::chart_title(config_set),
+        );
+
+        chart_info.warnings = arg_obj
+            .device
+            .warnings(config_set)
+            .into_iter()
+            .map(|warning| warning.within("device"))
+            .collect();
+
+        chart_info
+            .warnings
+            .extend(rejected_samples_warning(rejected_samples, arg_obj.sample_count));
+
+        ok_to_js(&UncertaintyInfo {
+            chart_info,
+            bands,
+            valid_samples,
+            rejected_samples,
+        })
+    };
+
+    trace_boundary(TraceAction::Exit);
+    result
+}
+
+/***********************************************************************************************************************
+ * Rejected samples truncate the distribution of the perturbed arguments, so the caller is warned unless they are few
+ */
+fn rejected_samples_warning(rejected_samples: u16, sample_count: u16) -> Option<GenericError> {
+    let rejected_fraction = rejected_samples as f64 / sample_count as f64;
+
+    if rejected_fraction > REJECTED_SAMPLES_WARNING {
+        Some(
+            GenericError::new(
+                ErrorCode::ModelValidity,
+                format!(
+                    "{} of the {} Monte Carlo samples fell outside the permitted range of an argument, so the \
+                     uncertainty bands exclude {:.1}% of the requested distribution",
+                    rejected_samples,
+                    sample_count,
+                    100.0 * rejected_fraction
+                ),
+            )
+            .for_field("tolerances")
+            .with_value(100.0 * rejected_fraction)
+            .in_units(constants::UNITS_PERCENT),
+        )
+    } else {
+        None
+    }
+}
+
+/***********************************************************************************************************************
+ * Check the analysis arguments themselves
+ */
+fn validate<T: DeviceTypeArgs>(arg_obj: &UncertaintyArgs<T>) -> Vec<GenericError> {
+    let mut errors: Vec<GenericError> = vec![];
+
+    if !SAMPLE_COUNT_RANGE.contains(arg_obj.sample_count) {
+        errors.push(GenericError::new_from_u16(SAMPLE_COUNT_RANGE, arg_obj.sample_count));
+    }
+
+    // Both percentiles share the same range
+    for (field, pct) in [
+        ("lower_percentile", arg_obj.lower_percentile),
+        ("upper_percentile", arg_obj.upper_percentile),
+    ] {
+        if !PERCENTILE_RANGE.contains(pct) {
+            errors.push(GenericError::new_from_f64(PERCENTILE_RANGE, pct).for_field(field));
+        }
+    }
+
+    if arg_obj.lower_percentile >= arg_obj.upper_percentile {
+        errors.push(
+            GenericError::new(
+                ErrorCode::Inconsistent,
+                format!(
+                    "Lower percentile {:?} must be less than upper percentile {:?}",
+                    arg_obj.lower_percentile, arg_obj.upper_percentile
+                ),
+            )
+            .for_field("lower_percentile")
+            .with_value(arg_obj.lower_percentile),
+        );
+    }
+
+    for (idx, tolerance) in arg_obj.tolerances.iter().enumerate() {
+        let nominal = arg_obj.device.numeric_field(&tolerance.field);
+
+        if nominal.is_none() {
+            errors.push(
+                GenericError::new(
+                    ErrorCode::UnknownId,
+                    format!("'{}' is not a numeric argument of this device", tolerance.field),
+                )
+                .for_field(&format!("tolerances.{}.field", idx)),
+            );
+        }
+
+        if tolerance.spread.is_nan() || tolerance.spread < 0.0 {
+            errors.push(
+                GenericError::new(
+                    ErrorCode::OutOfRange,
+                    format!(
+                        "Tolerance spread for '{}' must not be negative, not '{:?}'",
+                        tolerance.field, tolerance.spread
+                    ),
+                )
+                .for_field(&format!("tolerances.{}.spread", idx))
+                .with_value(tolerance.spread),
+            );
+        } else if let Some(nominal) = nominal {
+            let spread = metric_spread(tolerance, nominal);
+
+            if spread > 0.0 && is_integer_field(&arg_obj.device, &tolerance.field, nominal) && spread < 1.0 {
+                errors.push(
+                    GenericError::new(
+                        ErrorCode::OutOfRange,
+                        format!(
+                            "Tolerance spread for '{}' must not be less than 1 {}, since the argument only takes whole \
+                             number values",
+                            tolerance.field,
+                            metric_units(&tolerance.field)
+                        ),
+                    )
+                    .for_field(&format!("tolerances.{}.spread", idx))
+                    .with_value(tolerance.spread),
+                );
+            }
+        }
+    }
+
+    errors
+}
+
+/***********************************************************************************************************************
+ * The spread of a tolerance in the metric units of its argument
+ */
+fn metric_spread(tolerance: &Tolerance, nominal: f64) -> f64 {
+    if tolerance.relative {
+        nominal * tolerance.spread / 100.0
+    } else {
+        // An absolute spread is in the units of the client's request
+        match Quantity::of_field(&tolerance.field) {
+            Some(quantity) => units::request_units().interval_to_metric(quantity, tolerance.spread),
+            None => tolerance.spread,
+        }
+    }
+}
+
+// Integer arguments are rounded when set, so a fraction of a unit leaves them unchanged
+fn is_integer_field<T: DeviceTypeArgs>(device: &T, field: &str, nominal: f64) -> bool {
+    let mut probe = device.clone();
+    probe.set_numeric_field(field, nominal + 0.25);
+
+    probe.numeric_field(field) == Some(nominal)
+}
+
+fn metric_units(field: &str) -> &'static str {
+    match Quantity::of_field(field) {
+        Some(quantity) => UnitSystem::Metric.units(quantity),
+        None => units::field_units(field),
+    }
+}
+
+/***********************************************************************************************************************
+ * Create a copy of the device arguments in which each toleranced value has been randomly perturbed
+ */
+fn perturb<T: DeviceTypeArgs>(device: &T, tolerances: &[Tolerance], rng: &mut Prng) -> T {
+    let mut sample = device.clone();
+
+    for tolerance in tolerances {
+        let nominal = device.numeric_field(&tolerance.field).unwrap();
+        let spread = metric_spread(tolerance, nominal);
+
+        let offset = match tolerance.distribution {
+            Distribution::Uniform => (2.0 * rng.uniform() - 1.0) * spread,
+            Distribution::Normal => rng.standard_normal() * spread,
+        };
+
+        sample.set_numeric_field(&tolerance.field, nominal + offset);
+    }
+
+    sample
+}
+
+/***********************************************************************************************************************
+ * Linearly interpolated percentile of an already sorted, non-empty list of values
+ */
+fn percentile(sorted_values: &[f64], pct: f64) -> f64 {
+    let rank = (pct / 100.0) * (sorted_values.len() - 1) as f64;
+    let lower_idx = rank.floor() as usize;
+    let upper_idx = rank.ceil() as usize;
+
+    sorted_values[lower_idx] + (sorted_values[upper_idx] - sorted_values[lower_idx]) * (rank - lower_idx as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::devices::porous_absorber::PorousAbsorberArgs;
+
+    fn uncertainty_args(tolerances: serde_json::Value) -> UncertaintyArgs<PorousAbsorberArgs> {
+        serde_json::from_value(json!({
+            "device": {
+                "absorber_thickness_mm": 30,
+                "flow_resistivity": 16500,
+                "air_gap_mm": 100,
+                "angle": 0,
+                "graph_start_freq": 62.5,
+                "smooth_curve": false,
+                "subdivision": 3,
+                "show_diagram": false,
+                "air_temp": 20,
+                "air_pressure": 1.0,
+            },
+            "tolerances": tolerances,
+        }))
+        .unwrap()
+    }
+
+    fn tolerance(field: &str, spread: f64, relative: bool) -> serde_json::Value {
+        json!({ "field": field, "distribution": "uniform", "spread": spread, "relative": relative })
+    }
+
+    #[test]
+    fn refuses_tolerance_below_resolution_of_integer_field() {
+        let errors = validate(&uncertainty_args(json!([
+            tolerance("absorber_thickness_mm", 0.5, false),
+            tolerance("air_gap_mm", 0.5, true),
+        ])));
+
+        assert_eq!(errors.len(), 2);
+        assert!(errors.iter().all(|err| err.code == ErrorCode::OutOfRange));
+        assert_eq!(errors[0].field.as_deref(), Some("tolerances.0.spread"));
+        assert_eq!(errors[1].field.as_deref(), Some("tolerances.1.spread"));
+    }
+
+    #[test]
+    fn accepts_whole_unit_and_fractional_tolerances() {
+        let errors = validate(&uncertainty_args(json!([
+            tolerance("absorber_thickness_mm", 1.0, false),
+            tolerance("air_gap_mm", 5.0, true),
+            tolerance("air_pressure", 0.001, false),
+            tolerance("angle", 0.0, false),
+        ])));
+
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+
+    #[test]
+    fn perturbs_integer_field_in_whole_units() {
+        let arg_obj = uncertainty_args(json!([tolerance("absorber_thickness_mm", 5.0, false)]));
+        let mut rng = Prng::new(1);
+
+        let values: Vec<f64> = (0..100)
+            .map(|_| {
+                perturb(&arg_obj.device, &arg_obj.tolerances, &mut rng)
+                    .numeric_field("absorber_thickness_mm")
+                    .unwrap()
+            })
+            .collect();
+
+        assert!(values.iter().all(|value| (25.0..=35.0).contains(value)));
+        assert!(values.iter().any(|value| *value < 28.0) && values.iter().any(|value| *value > 32.0));
+    }
+
+    #[test]
+    fn warns_when_samples_are_rejected() {
+        assert!(rejected_samples_warning(0, 200).is_none());
+        assert!(rejected_samples_warning(2, 200).is_none());
+
+        let warning = rejected_samples_warning(50, 200).unwrap();
+
+        assert_eq!(warning.code, ErrorCode::ModelValidity);
+        assert_eq!(warning.value, Some(25.0));
+    }
+}
@@ -0,0 +1,85 @@
+/***********************************************************************************************************************
+ * Porous Absorber Calculator - Project file command line tool
+ *
+ * Usage: porous_absorber_project [--trace <trace file>] <save | load | migrate> <project file>
+ *
+ *   save     Check every design in a current version project file, then write it out again
+ *   load     Read a project file of any schema version, migrating and checking it
+ *   migrate  Upgrade a project file to the current schema version without checking its designs
+ *
+ *   --trace  Write the trace output of every module to the given file
+ *
+ * The resulting project document is written to stdout.  Validation errors are written to stderr as a JSON list having
+ * the same structure as the errors returned by the WASM functions
+ *
+ * (c) Chris Whealy 2020, 2024
+ */
+use std::{env, fs, path::Path, process::ExitCode};
+
+use porous_absorber_calculator::project;
+use serde::Serialize;
+use serde_json::Value;
+
+const USAGE: &str = "Usage: porous_absorber_project [--trace <trace file>] <save | load | migrate> <project file>";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().collect();
+
+    let (trace_path, command, path) = match args.as_slice() {
+        [_, command, path] => (None, command.as_str(), path.as_str()),
+        [_, option, trace_path, command, path] if option == "--trace" => {
+            (Some(trace_path), command.as_str(), path.as_str())
+        },
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::FAILURE;
+        },
+    };
+
+    if let Some(trace_path) = trace_path {
+        if let Err(err) = porous_absorber_calculator::trace_to_file(Path::new(trace_path)) {
+            eprintln!("Unable to create trace file {}: {}", trace_path, err);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    let project_json = match fs::read_to_string(path) {
+        Ok(project_json) => project_json,
+        Err(err) => {
+            eprintln!("Unable to read {}: {}", path, err);
+            return ExitCode::FAILURE;
+        },
+    };
+
+    let result = match command {
+        "save" => read_json(&project_json).and_then(|document| project::save(document).map_err(errors_json)),
+        "load" => project::load(&project_json)
+            .map(|project| serde_json::to_string_pretty(&project).unwrap())
+            .map_err(errors_json),
+        "migrate" => read_json(&project_json).and_then(|document| {
+            project::migrate(document)
+                .map(|migrated| serde_json::to_string_pretty(&migrated).unwrap())
+                .map_err(|err| errors_json(vec![err]))
+        }),
+        _ => Err(format!("Unknown command '{}'\n{}", command, USAGE)),
+    };
+
+    match result {
+        Ok(project_json) => {
+            println!("{}", project_json);
+            ExitCode::SUCCESS
+        },
+        Err(errors) => {
+            eprintln!("{}", errors);
+            ExitCode::FAILURE
+        },
+    }
+}
+
+fn read_json(project_json: &str) -> Result<Value, String> {
+    serde_json::from_str(project_json).map_err(|err| err.to_string())
+}
+
+fn errors_json<E: Serialize>(errors: Vec<E>) -> String {
+    serde_json::to_string_pretty(&errors).unwrap()
+}
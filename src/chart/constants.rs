@@ -21,6 +21,19 @@ pub const RGB_LIGHT_PINK: &str = "rgb(246, 195, 203)";
 pub const RGB_DARK_BLUE: &str = "rgb(6, 1, 123)";
 pub const RGB_GREEN: &str = "rgb(20, 255, 20)";
 pub const RGB_OFF_WHITE: &str = "rgb(255, 255, 238)";
+pub const RGB_ORANGE: &str = "rgb(255, 140, 0)";
+pub const RGB_TEAL: &str = "rgb(0, 128, 128)";
+pub const RGB_GREY: &str = "rgb(110, 110, 110)";
+pub const RGB_LIGHT_GREY: &str = "rgb(200, 200, 190)";
+pub const RGB_MAJOR_GRID: &str = "rgb(190, 190, 190)";
+pub const RGB_MINOR_GRID: &str = "rgb(228, 228, 228)";
+
+// Measured datasets are plotted in these colours, which are reused if there are more datasets than colours
+pub const MEASURED_DATA_COLOURS: [&str; 4] = [RGB_BLACK, RGB_ORANGE, RGB_TEAL, RGB_GREY];
+
+// Series in a comparison of several designs are plotted in these colours, which are reused if necessary
+pub const COMPARISON_COLOURS: [&str; 7] =
+    [RGB_PINK, RGB_DARK_BLUE, RGB_GREEN, RGB_ORANGE, RGB_TEAL, RGB_BLACK, RGB_GREY];
 
 pub const BASE_TYPEFACE: &str = "Arial";
 pub const TITLE_FONT_SIZE: f64 = 36.0;
@@ -31,6 +44,7 @@ pub const LABEL_FONT_SIZE: f64 = 20.0;
  */
 pub const CHART_TITLE_OVERALL_ABS: &str = "Overall Absorption";
 pub const CHART_TITLE_NORMAL_INCIDENCE: &str = "Normal Incidence Absorption";
+pub const CHART_TITLE_COMPARISON: &str = "Absorption Comparison";
 
 pub fn chart_title_at_incident_angle(title: &str, angle: u16) -> String {
     format!("{} at {}°", title, angle)
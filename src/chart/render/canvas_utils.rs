@@ -7,10 +7,21 @@ use libm::{pow, sqrt};
 use wasm_bindgen::JsCast;
 
 use crate::{
-    chart::render::constants::{BOTTOM_MARGIN_INSET, RIGHT_MARGIN_INSET, X_AXIS_INSET},
+    chart::render::constants::{BOTTOM_MARGIN_INSET, GRAPH_CANVAS_ID, RIGHT_MARGIN_INSET, X_AXIS_INSET},
     config::chart::PlotAbsPoint,
 };
 
+pub fn graph_canvas() -> web_sys::HtmlCanvasElement {
+    web_sys::window()
+        .unwrap()
+        .document()
+        .unwrap()
+        .get_element_by_id(GRAPH_CANVAS_ID)
+        .unwrap()
+        .dyn_into::<web_sys::HtmlCanvasElement>()
+        .unwrap()
+}
+
 pub fn get_2d_context(canvas: &web_sys::HtmlCanvasElement) -> web_sys::CanvasRenderingContext2d {
     canvas
         .get_context("2d")
@@ -28,9 +28,15 @@ pub const TICK_LENGTH: f64 = 10.0;
 pub const TICK_LABEL_GAP: f64 = 5.0;
 pub const PLOT_POINT_RADIUS: f64 = 5.0;
 
+// Opacity of the shaded envelope drawn around a series
+pub const ENVELOPE_ALPHA: f64 = 0.2;
+
 pub const TITLE_KEY_GAP: f64 = 50.0;
 pub const KEY_SYMBOL_LENGTH: f64 = 30.0;
 pub const SYMBOL_TEXT_GAP: f64 = 10.0;
 
 // Scale factor for magnifying the holes in a micro-perforated panel
 pub const MP_SCALE_FACTOR: f64 = 20.0;
+
+// Vertical gap between a device thumbnail and its label
+pub const THUMBNAIL_GAP: f64 = 5.0;
@@ -3,6 +3,7 @@
  *
  * (c) Chris Whealy 2020, 2024
  */
+use libm::{ceil, floor, log10, pow};
 use wasm_bindgen::{JsCast, JsValue};
 
 use crate::{
@@ -12,7 +13,7 @@ use crate::{
     },
     config::chart::*,
     trace::*,
-    utils::maths_functions::TAU,
+    utils::maths_functions::{round_2dp, TAU},
 };
 use crate::devices::generic_device::{DeviceType, GenericDeviceInfo};
 use crate::trace::trace_flags::trace_flag_for;
@@ -35,7 +36,77 @@ struct ImageSubdiv {
 const QUARTER_TURN: f64 = TAU / 4.0;
 
 pub fn device_diagram(device: &GenericDeviceInfo, widest_y_tick_label: f64, y_axis_length: &f64, y_axis_inset: &f64) {
-    const FN_NAME: &str = "device_diagram";
+    draw_device(
+        device,
+        &PlotPoint {
+            x: render::constants::LEFT_MARGIN_INSET,
+            y: render::constants::X_AXIS_INSET,
+        },
+        *y_axis_length,
+        diagram_width(widest_y_tick_label, y_axis_inset),
+    );
+}
+
+/***********************************************************************************************************************
+ * Draw a thumbnail diagram of each device in a comparison, one above the other, each headed by its label
+ */
+pub fn device_thumbnails(
+    devices: &[(&str, &GenericDeviceInfo)],
+    widest_y_tick_label: f64,
+    y_axis_length: &f64,
+    y_axis_inset: &f64,
+) {
+    const FN_NAME: &str = "device_thumbnails";
+    let trace_active = trace_flag_for(MOD_NAME);
+    let trace_boundary = make_boundary_trace_fn(trace_active, MOD_NAME, FN_NAME);
+    let trace = make_trace_fn(trace_active, MOD_NAME, FN_NAME);
+
+    trace_boundary(TraceAction::Enter);
+
+    if !devices.is_empty() {
+        let ctx = get_2d_context(&graph_canvas());
+        let label_font_size = crate::chart::constants::LABEL_FONT_SIZE;
+        let available_pxls = diagram_width(widest_y_tick_label, y_axis_inset);
+        let slot_height = *y_axis_length / devices.len() as f64;
+        let thumbnail_height = slot_height - label_font_size - render::constants::THUMBNAIL_GAP;
+
+        trace(format!("Drawing {} thumbnails {} px high", devices.len(), thumbnail_height));
+
+        ctx.save();
+        ctx.set_font(&crate::chart::constants::FONT_METADATA_LABEL.font());
+
+        for (idx, (label, device)) in devices.iter().enumerate() {
+            let slot_top = render::constants::X_AXIS_INSET + idx as f64 * slot_height;
+
+            ctx.fill_text(
+                label,
+                render::constants::LEFT_MARGIN_INSET - render::constants::WALL_IMG_WIDTH,
+                slot_top + label_font_size,
+            )
+            .unwrap();
+
+            draw_device(
+                device,
+                &PlotPoint {
+                    x: render::constants::LEFT_MARGIN_INSET,
+                    y: slot_top + label_font_size + render::constants::THUMBNAIL_GAP,
+                },
+                thumbnail_height,
+                available_pxls,
+            );
+        }
+
+        ctx.restore();
+    }
+
+    trace_boundary(TraceAction::Exit);
+}
+
+/***********************************************************************************************************************
+ * Draw a device diagram of the given height with the face of the wall at the given top left position
+ */
+fn draw_device(device: &GenericDeviceInfo, top_left: &PlotPoint, height: f64, available_pxls: f64) {
+    const FN_NAME: &str = "draw_device";
     let trace_active = trace_flag_for(MOD_NAME);
     let trace_boundary = make_boundary_trace_fn(trace_active, MOD_NAME, FN_NAME);
     let trace = make_trace_fn(trace_active, MOD_NAME, FN_NAME);
@@ -58,38 +129,40 @@ pub fn device_diagram(device: &GenericDeviceInfo, widest_y_tick_label: f64, y_ax
         None => 0.0,
     };
 
-    let (panel_thickness_mm, between_voids_mm, void_mm) = match device.device_type {
+    // Holes in alternate rows of a staggered pattern are offset from the rows that lie in the plane of the diagram
+    let (panel_thickness_mm, void_pitch_mm, void_mm, offset_rows) = match device.device_type {
         DeviceType::SlottedPanelAbsorber => {
             let panel = device.sl_panel.unwrap();
-            (panel.thickness_mm, panel.slot_distance_mm, panel.slot_width_mm)
+            (
+                panel.thickness_mm,
+                panel.slot_distance_mm + panel.slot_width_mm,
+                panel.slot_width_mm,
+                false,
+            )
         },
         DeviceType::PerforatedPanelAbsorber => {
             let panel = device.pf_panel.unwrap();
             (
                 panel.thickness_mm,
-                panel.hole_centres_mm - (2.0 * panel.hole_radius_mm),
+                panel.hole_pattern.row_pitch(panel.hole_centres_mm),
                 2.0 * panel.hole_radius_mm,
+                panel.hole_pattern.is_staggered(),
             )
         },
         DeviceType::MicroperforatedPanelAbsorber => {
             let panel = device.mp_panel.unwrap();
             (
                 panel.thickness_mm,
-                panel.hole_centres_mm - (2.0 * panel.hole_radius_mm),
+                panel.hole_pattern.row_pitch(panel.hole_centres_mm),
                 2.0 * panel.hole_radius_mm,
+                panel.hole_pattern.is_staggered(),
             )
         },
-        DeviceType::RigidBackedPorousAbsorber => (0.0, 0.0, 0.0),
+        DeviceType::RigidBackedPorousAbsorber => (0.0, 0.0, 0.0, false),
     };
 
     let dev_depth_mm = air_gap_mm + absorber_thickness_mm + panel_thickness_mm;
 
-    // Calculate the amount of space available for the diagram
-    let available_pxls = y_axis_name_x_pos(widest_y_tick_label, y_axis_inset)
-        - render::constants::LEFT_MARGIN_INSET
-        - render::constants::WALL_IMG_WIDTH
-        - crate::chart::constants::LABEL_FONT_SIZE;
-
     let horiz_pixels_per_mm = if dev_depth_mm > available_pxls { available_pxls / dev_depth_mm } else { 1.0 };
 
     trace(format!("Overall device depth = {} mm", dev_depth_mm));
@@ -104,8 +177,8 @@ pub fn device_diagram(device: &GenericDeviceInfo, widest_y_tick_label: f64, y_ax
 
     // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
     // Draw fixed wall image
-    let wall_pos_x = render::constants::LEFT_MARGIN_INSET - render::constants::WALL_IMG_WIDTH;
-    let wall_pos_y = render::constants::X_AXIS_INSET;
+    let wall_pos_x = top_left.x - render::constants::WALL_IMG_WIDTH;
+    let wall_pos_y = top_left.y;
 
     trace(format!("Drawing wall at location ({},{})", wall_pos_x, wall_pos_y));
 
@@ -115,17 +188,17 @@ pub fn device_diagram(device: &GenericDeviceInfo, widest_y_tick_label: f64, y_ax
         PlotPoint { x: wall_pos_x, y: wall_pos_y },
         DimensionPair {
             width: render::constants::WALL_IMG_WIDTH,
-            height: *y_axis_length,
+            height,
         },
     );
 
     // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
     // Draw an optional absorber layer - this layer is absent for the microperforated panel device
     // Firefox crashes if you attempt to draw a zero-width image, but Chrome and Brave are fine with this
-    let half_height = *y_axis_length / 2.0;
+    let half_height = height / 2.0;
 
-    let abs_pos_x = render::constants::LEFT_MARGIN_INSET + (air_gap_mm * horiz_pixels_per_mm);
-    let abs_pos_y = render::constants::X_AXIS_INSET;
+    let abs_pos_x = top_left.x + (air_gap_mm * horiz_pixels_per_mm);
+    let abs_pos_y = top_left.y;
     let abs_width_px = absorber_thickness_mm * horiz_pixels_per_mm;
 
     // Do we need to draw an absorber?
@@ -164,7 +237,7 @@ pub fn device_diagram(device: &GenericDeviceInfo, widest_y_tick_label: f64, y_ax
                         height: half_height,
                     },
                     top_left: PlotPoint {
-                        x: render::constants::LEFT_MARGIN_INSET,
+                        x: top_left.x,
                         y: abs_pos_y + half_height,
                     },
                     dims: DimensionPair {
@@ -180,15 +253,9 @@ pub fn device_diagram(device: &GenericDeviceInfo, widest_y_tick_label: f64, y_ax
                 &absorber_img,
                 ImageSubdiv {
                     sub_top_left: render::constants::ORIGIN,
-                    sub_dims: DimensionPair {
-                        width: abs_width_px,
-                        height: *y_axis_length,
-                    },
+                    sub_dims: DimensionPair { width: abs_width_px, height },
                     top_left: PlotPoint { x: abs_pos_x, y: abs_pos_y },
-                    dims: DimensionPair {
-                        width: abs_width_px,
-                        height: *y_axis_length,
-                    },
+                    dims: DimensionPair { width: abs_width_px, height },
                 },
             );
         }
@@ -202,7 +269,7 @@ pub fn device_diagram(device: &GenericDeviceInfo, widest_y_tick_label: f64, y_ax
     // Firefox crashes if you attempt to draw a zero-width image, but Chrome and Brave are fine with this
     let panel_width_px = panel_thickness_mm * horiz_pixels_per_mm;
     let panel_pos_x = abs_pos_x + abs_width_px;
-    let panel_pos_y = render::constants::X_AXIS_INSET;
+    let panel_pos_y = top_left.y;
 
     // Do we need to draw a panel?
     if panel_thickness_mm > 0.0 {
@@ -214,15 +281,9 @@ pub fn device_diagram(device: &GenericDeviceInfo, widest_y_tick_label: f64, y_ax
             &panel_img,
             ImageSubdiv {
                 sub_top_left: render::constants::ORIGIN,
-                sub_dims: DimensionPair {
-                    width: panel_width_px,
-                    height: *y_axis_length,
-                },
+                sub_dims: DimensionPair { width: panel_width_px, height },
                 top_left: PlotPoint { x: panel_pos_x, y: panel_pos_y },
-                dims: DimensionPair {
-                    width: panel_width_px,
-                    height: *y_axis_length,
-                },
+                dims: DimensionPair { width: panel_width_px, height },
             },
         );
 
@@ -235,17 +296,22 @@ pub fn device_diagram(device: &GenericDeviceInfo, widest_y_tick_label: f64, y_ax
 
         // "void" represents the size of either the hole or the slot in the panel
         let bg_colour = JsValue::from(crate::chart::constants::RGB_OFF_WHITE);
+        let offset_colour = JsValue::from(crate::chart::constants::RGB_LIGHT_GREY);
         let scaled_void = scale_factor * void_mm;
-        let scaled_between_voids = scale_factor * between_voids_mm;
-        let interval = scaled_between_voids + scaled_void;
-        let mut gap_pos = panel_pos_y + scaled_between_voids;
+        let interval = scale_factor * void_pitch_mm;
+        let mut gap_pos = panel_pos_y + (interval - scaled_void).max(0.0);
+        let mut row = 0;
 
         trace(format!("Voids centred every = {} mm", interval));
 
         // Draw background-coloured blocks over the panel to indicate the position and width of the voids
-        while gap_pos < (panel_pos_y + *y_axis_length) {
-            draw_box(&ctx, &panel_pos_x, &gap_pos, &panel_width_px, &scaled_void, &bg_colour);
+        // Voids in offset rows are drawn in a lighter colour
+        while gap_pos < (panel_pos_y + height) {
+            let colour = if offset_rows && row % 2 == 1 { &offset_colour } else { &bg_colour };
+
+            draw_box(&ctx, &panel_pos_x, &gap_pos, &panel_width_px, &scaled_void, colour);
             gap_pos += interval;
+            row += 1;
         }
     } else {
         // Nope, no panels here...
@@ -294,15 +360,11 @@ pub fn title_and_key(
     ctx.set_font(&key_font.font());
     ctx.set_stroke_style(&JsValue::from(key_font.stroke_style));
 
-    // It is assumed that there will be no more than 6 series to plot on the same graph
+    // The key has at most 3 columns, except that 4 entries are arranged as a 2x2 table
     let (mut key_rows, mut key_columns): (usize, usize) = match series_list.len() {
-        1 => (1, 1),
-        2 => (1, 2),
-        3 => (1, 3),
+        0 => (0, 0),
         4 => (2, 2),
-        5 => (2, 3),
-        6 => (2, 3),
-        _ => (0, 0),
+        n => (n.div_ceil(3), n.min(3)),
     };
 
     // Find the length of the longest key text
@@ -325,7 +387,7 @@ pub fn title_and_key(
     trace(format!("available_key_width = {}", available_key_width));
     trace(format!("required_key_width  = {}", required_key_width));
 
-    if required_key_width > available_key_width {
+    if required_key_width > available_key_width && key_columns > 1 {
         key_columns -= 1;
         key_rows = (series_list.len() as f64 / key_columns as f64).ceil() as usize;
         required_key_width = key_entry_width * key_columns as f64;
@@ -387,6 +449,8 @@ pub fn title_and_key(
  * The widest tick label value is needed as part of the calculation to determine the available width within which to
  * draw the device diagram
  */
+const Y_AXIS_INTERVALS: usize = 10;
+
 pub fn axes(canvas: &web_sys::HtmlCanvasElement, chart_cfg: &ChartConfig, y_axis_inset: &f64) -> (ChartBox, f64) {
     const FN_NAME: &str = "axes";
     let trace_active = trace_flag_for(MOD_NAME);
@@ -400,6 +464,22 @@ pub fn axes(canvas: &web_sys::HtmlCanvasElement, chart_cfg: &ChartConfig, y_axis
         y: canvas.height() as f64 - (2.0 * render::constants::TOP_MARGIN_INSET),
     };
 
+    let y_axis_end_point = PlotPoint {
+        x: *y_axis_inset,
+        y: render::constants::X_AXIS_INSET,
+    };
+
+    let x_axis_end_point = PlotPoint {
+        x: canvas.width() as f64 - render::constants::RIGHT_MARGIN_INSET,
+        y: canvas.height() as f64 - render::constants::X_AXIS_INSET,
+    };
+
+    let chart_box = ChartBox {
+        top_left: y_axis_end_point.clone(),
+        bottom_right: x_axis_end_point.clone(),
+        view: chart_cfg.view,
+    };
+
     let label_font = &FontMetadata {
         typeface: &crate::chart::constants::BASE_TYPEFACE,
         font_size: crate::chart::constants::LABEL_FONT_SIZE,
@@ -410,24 +490,14 @@ pub fn axes(canvas: &web_sys::HtmlCanvasElement, chart_cfg: &ChartConfig, y_axis
     // Draw Y axis
     trace("Drawing Y axis".to_string());
 
-    let abs_strs = vec![
-        "0.0".to_string(),
-        "0.1".to_string(),
-        "0.2".to_string(),
-        "0.3".to_string(),
-        "0.4".to_string(),
-        "0.5".to_string(),
-        "0.6".to_string(),
-        "0.7".to_string(),
-        "0.8".to_string(),
-        "0.9".to_string(),
-        "1.0".to_string(),
-    ];
+    // The Y axis is divided into ten intervals, each labelled to enough decimal places to distinguish them.  A small
+    // tolerance stops an interval that is an exact power of ten, such as 0.1, from gaining an extra decimal place
+    let y_tick_interval = (chart_cfg.view.abs_max - chart_cfg.view.abs_min) / Y_AXIS_INTERVALS as f64;
+    let y_decimal_places = (-floor(log10(y_tick_interval) + 1.0e-9)).max(0.0) as usize;
 
-    let y_axis_end_point = PlotPoint {
-        x: *y_axis_inset,
-        y: render::constants::X_AXIS_INSET,
-    };
+    let abs_strs: Vec<String> = (0..=Y_AXIS_INTERVALS)
+        .map(|idx| format!("{:.*}", y_decimal_places, chart_cfg.view.abs_min + idx as f64 * y_tick_interval))
+        .collect();
 
     let widest_tick_label = draw_axis(
         &canvas,
@@ -445,41 +515,61 @@ pub fn axes(canvas: &web_sys::HtmlCanvasElement, chart_cfg: &ChartConfig, y_axis
     // Draw X axis
     trace("Drawing X axis".to_string());
 
-    let freq_strs: Vec<String> = chart_cfg.frequencies.iter().fold(vec![], |mut acc, f| {
-        acc.push(if (*f - 62.5).abs() < f64::EPSILON {
-            "62.5".to_string()
-        } else {
-            format!("{}", f.round() as u32)
-        });
-        acc
-    });
+    if chart_cfg.decade_axis {
+        draw_log_frequency_axis(canvas, &chart_box, label_font);
+    } else {
+        // The rotated tick labels overlap if the ticks are closer together than the label font height, in which case
+        // only every nth tick is labelled
+        let x_tick_interval = (x_axis_end_point.x - chart_origin.x) / (chart_cfg.frequencies.len() - 1) as f64;
+        let label_step = (label_font.font_size / x_tick_interval).ceil().max(1.0) as usize;
+        trace(format!("Labelling every {} frequency ticks", label_step));
+
+        let freq_strs: Vec<String> = chart_cfg
+            .frequency_labels
+            .iter()
+            .enumerate()
+            .map(|(idx, label)| if idx % label_step == 0 { label.clone() } else { String::new() })
+            .collect();
+
+        draw_axis(
+            &canvas,
+            Axis {
+                title: crate::chart::constants::TXT_X_AXIS_TITLE,
+                start_point: &chart_origin,
+                end_point: &x_axis_end_point,
+                values: freq_strs,
+                orientation: AxisOrientation::Horizontal,
+                label_font,
+            },
+        );
+    }
 
-    let x_axis_end_point = PlotPoint {
-        x: canvas.width() as f64 - render::constants::RIGHT_MARGIN_INSET,
-        y: canvas.height() as f64 - render::constants::X_AXIS_INSET,
-    };
+    trace_boundary(TraceAction::Exit);
 
-    draw_axis(
-        &canvas,
-        Axis {
-            title: crate::chart::constants::TXT_X_AXIS_TITLE,
-            start_point: &chart_origin,
-            end_point: &x_axis_end_point,
-            values: freq_strs,
-            orientation: AxisOrientation::Horizontal,
-            label_font,
-        },
-    );
+    (chart_box, widest_tick_label)
+}
 
-    trace_boundary(TraceAction::Exit);
+/***********************************************************************************************************************
+ * Restrict subsequent drawing to the chart box so that a zoomed or panned view does not draw over the axes
+ *
+ * Each call must be paired with a call to remove_clip()
+ */
+pub fn clip_to_chart_box(canvas: &web_sys::HtmlCanvasElement, chart_box: &ChartBox) {
+    let ctx = get_2d_context(canvas);
 
-    (
-        ChartBox {
-            top_left: y_axis_end_point,
-            bottom_right: x_axis_end_point,
-        },
-        widest_tick_label,
-    )
+    ctx.save();
+    ctx.begin_path();
+    ctx.rect(
+        chart_box.top_left.x,
+        chart_box.top_left.y,
+        chart_box.bottom_right.x_diff(&chart_box.top_left),
+        chart_box.bottom_right.y_diff(&chart_box.top_left),
+    );
+    ctx.clip();
+}
+
+pub fn remove_clip(canvas: &web_sys::HtmlCanvasElement) {
+    get_2d_context(canvas).restore();
 }
 
 /***********************************************************************************************************************
@@ -490,9 +580,7 @@ pub fn splines(
     mut abs_points: Vec<PlotAbsPoint>,
     stroke_colour: &JsValue,
     smooth_curve: &bool,
-    x_axis_length: &f64,
-    y_axis_length: &f64,
-    y_axis_inset: &f64,
+    chart_box: &ChartBox,
 ) -> Vec<PlotAbsPoint> {
     const FN_NAME: &str = "splines";
     let trace_active = trace_flag_for(MOD_NAME);
@@ -503,13 +591,12 @@ pub fn splines(
 
     let ctx = get_2d_context(&canvas);
 
-    let x_tick_interval = x_axis_length / (abs_points.len() - 1) as f64;
-    let y_pos = scaled_y_pos(canvas.height() as f64 - render::constants::X_AXIS_INSET, *y_axis_length);
-
     // The frequency and absorption values need to be translated into canvas coordinates
-    for (idx, abs_point) in abs_points.iter_mut().enumerate() {
-        abs_point.at.x = y_axis_inset + x_tick_interval * idx as f64;
-        abs_point.at.y = y_pos(abs_point.abs);
+    // The absorption value handed back to JavaScript is rounded to 2dp
+    for abs_point in abs_points.iter_mut() {
+        abs_point.at.x = chart_box.x_pos(abs_point.freq);
+        abs_point.at.y = chart_box.y_pos(abs_point.abs);
+        abs_point.abs = round_2dp(abs_point.abs);
 
         trace(format!(
             "PlotPoint(At: {}, freq: {}, abs: {})",
@@ -554,7 +641,130 @@ pub fn splines(
     draw_curved_path(&ctx, &cps, &abs_points, &stroke_colour);
 
     trace_boundary(TraceAction::Exit);
+
+    // Only the points visible within the chart box are handed back to JavaScript
     abs_points
+        .into_iter()
+        .filter(|abs_point| chart_box.contains(&abs_point.at))
+        .collect()
+}
+
+/***********************************************************************************************************************
+ * Draw a shaded envelope between a lower and an upper curve
+ *
+ * The envelope vertices are positioned in the same way as splines() so that the envelope surrounds its series
+ */
+pub fn envelope(
+    canvas: &web_sys::HtmlCanvasElement,
+    frequencies: &[f64],
+    lower: &[f64],
+    upper: &[f64],
+    fill_colour: &JsValue,
+    chart_box: &ChartBox,
+) {
+    const FN_NAME: &str = "envelope";
+    let trace_active = trace_flag_for(MOD_NAME);
+    let trace_boundary = make_boundary_trace_fn(trace_active, MOD_NAME, FN_NAME);
+
+    trace_boundary(TraceAction::Enter);
+
+    // As long as we have at least two points...
+    if upper.len() >= 2 && lower.len() == upper.len() && frequencies.len() == upper.len() {
+        let ctx = get_2d_context(canvas);
+        let x_pos = |freq: f64| chart_box.x_pos(freq);
+        let y_pos = |abs: f64| chart_box.y_pos(abs);
+
+        ctx.save();
+        ctx.set_global_alpha(render::constants::ENVELOPE_ALPHA);
+        ctx.set_fill_style(fill_colour);
+
+        // Trace along the upper curve from left to right, then back along the lower curve from right to left
+        ctx.begin_path();
+        ctx.move_to(x_pos(frequencies[0]), y_pos(upper[0]));
+
+        for (freq, abs) in frequencies.iter().zip(upper.iter()).skip(1) {
+            ctx.line_to(x_pos(*freq), y_pos(*abs));
+        }
+
+        for (freq, abs) in frequencies.iter().zip(lower.iter()).rev() {
+            ctx.line_to(x_pos(*freq), y_pos(*abs));
+        }
+
+        ctx.close_path();
+        ctx.fill();
+        ctx.restore();
+    }
+
+    trace_boundary(TraceAction::Exit);
+}
+
+/***********************************************************************************************************************
+ * Draw measured data points that have been interpolated onto the chart frequencies
+ *
+ * Measured points are drawn as hollow squares joined by a thin line so that they cannot be mistaken for a calculated
+ * series.  Frequencies outside the measured range have no value and are skipped
+ */
+pub fn measured_points(
+    canvas: &web_sys::HtmlCanvasElement,
+    frequencies: &[f64],
+    measured: &[Option<f64>],
+    stroke_colour: &JsValue,
+    chart_box: &ChartBox,
+) -> Vec<PlotAbsPoint> {
+    const FN_NAME: &str = "measured_points";
+    let trace_active = trace_flag_for(MOD_NAME);
+    let trace_boundary = make_boundary_trace_fn(trace_active, MOD_NAME, FN_NAME);
+    let trace = make_trace_fn(trace_active, MOD_NAME, FN_NAME);
+
+    trace_boundary(TraceAction::Enter);
+
+    let ctx = get_2d_context(canvas);
+
+    let plot_points: Vec<PlotAbsPoint> = frequencies
+        .iter()
+        .zip(measured.iter())
+        .filter_map(|(freq, abs)| {
+            abs.map(|abs| PlotAbsPoint {
+                at: PlotPoint {
+                    x: chart_box.x_pos(*freq),
+                    y: chart_box.y_pos(abs),
+                },
+                freq: *freq,
+                abs: round_2dp(abs),
+            })
+        })
+        .collect();
+
+    trace(format!("Drawing {} measured points", plot_points.len()));
+
+    ctx.save();
+    ctx.set_line_width(1.0);
+    ctx.set_stroke_style(stroke_colour);
+
+    for pair in plot_points.windows(2) {
+        ctx.begin_path();
+        ctx.move_to(pair[0].at.x, pair[0].at.y);
+        ctx.line_to(pair[1].at.x, pair[1].at.y);
+        ctx.stroke();
+    }
+
+    for plot_point in plot_points.iter() {
+        ctx.stroke_rect(
+            plot_point.at.x - render::constants::PLOT_POINT_RADIUS,
+            plot_point.at.y - render::constants::PLOT_POINT_RADIUS,
+            2.0 * render::constants::PLOT_POINT_RADIUS,
+            2.0 * render::constants::PLOT_POINT_RADIUS,
+        );
+    }
+
+    ctx.restore();
+
+    trace_boundary(TraceAction::Exit);
+
+    plot_points
+        .into_iter()
+        .filter(|plot_point| chart_box.contains(&plot_point.at))
+        .collect()
 }
 
 //**********************************************************************************************************************
@@ -663,6 +873,121 @@ fn draw_axis(canvas: &web_sys::HtmlCanvasElement, axis_info: Axis) -> f64 {
 
     widest_tick_label
 }
+
+/***********************************************************************************************************************
+ * Draw a logarithmic frequency axis
+ *
+ * Major gridlines are drawn at each decade and minor gridlines at each integer multiple within a decade.  Decades are
+ * always labelled, but if the axis spans less than two decades, the 2x and 5x multiples are labelled as well
+ */
+fn draw_log_frequency_axis(canvas: &web_sys::HtmlCanvasElement, chart_box: &ChartBox, label_font: &FontMetadata) {
+    const FN_NAME: &str = "draw_log_frequency_axis";
+    let trace_active = trace_flag_for(MOD_NAME);
+    let trace_boundary = make_boundary_trace_fn(trace_active, MOD_NAME, FN_NAME);
+    let trace = make_trace_fn(trace_active, MOD_NAME, FN_NAME);
+
+    trace_boundary(TraceAction::Enter);
+
+    let ctx = get_2d_context(canvas);
+    let view = &chart_box.view;
+    let start_point = &PlotPoint {
+        x: chart_box.top_left.x,
+        y: chart_box.bottom_right.y,
+    };
+    let end_point = &chart_box.bottom_right;
+    let (_, mid_width, bottom_margin_pos, _, _) = canvas_dimensions(canvas, &start_point.x);
+
+    let first_decade = floor(log10(view.freq_min)) as i32;
+    let last_decade = ceil(log10(view.freq_max)) as i32;
+    let label_minors = log10(view.freq_max / view.freq_min) < 2.0;
+
+    // Allow for rounding errors when deciding whether a gridline falls within the axis
+    let in_range = |freq: f64| freq >= view.freq_min * (1.0 - 1.0e-9) && freq <= view.freq_max * (1.0 + 1.0e-9);
+
+    ctx.save();
+    ctx.set_font(&label_font.font());
+    ctx.set_line_width(1.0);
+
+    for decade in first_decade..=last_decade {
+        for multiple in 1..=9 {
+            let freq = multiple as f64 * pow(10.0, decade as f64);
+
+            if !in_range(freq) {
+                continue;
+            }
+
+            let x = chart_box.x_pos(freq);
+            let is_major = multiple == 1;
+
+            trace(format!("Gridline at {}Hz", freq));
+
+            // Gridline
+            draw_line(
+                &ctx,
+                &PlotPoint { x, y: start_point.y },
+                &PlotPoint { x, y: chart_box.top_left.y },
+                &JsValue::from(if is_major {
+                    crate::chart::constants::RGB_MAJOR_GRID
+                } else {
+                    crate::chart::constants::RGB_MINOR_GRID
+                }),
+            );
+
+            // Tick and label
+            let tick_length = if is_major {
+                render::constants::TICK_LENGTH
+            } else {
+                render::constants::TICK_LENGTH / 2.0
+            };
+
+            draw_line(
+                &ctx,
+                &PlotPoint { x, y: start_point.y },
+                &PlotPoint {
+                    x,
+                    y: start_point.y + tick_length,
+                },
+                &JsValue::from(label_font.stroke_style),
+            );
+
+            if is_major || (label_minors && (multiple == 2 || multiple == 5)) {
+                let label = decade_label(freq);
+                let label_width = ctx.measure_text(&label).unwrap().width();
+
+                let label_y = start_point.y
+                    + render::constants::TICK_LENGTH
+                    + render::constants::TICK_LABEL_GAP
+                    + label_font.font_size;
+
+                ctx.fill_text(&label, x - label_width / 2.0, label_y).unwrap();
+            }
+        }
+    }
+
+    // Axis line and title
+    draw_line(&ctx, start_point, end_point, &JsValue::from(label_font.stroke_style));
+
+    let axis_label_width = ctx.measure_text(crate::chart::constants::TXT_X_AXIS_TITLE).unwrap().width();
+    ctx.fill_text(
+        crate::chart::constants::TXT_X_AXIS_TITLE,
+        mid_width - (axis_label_width / 2.0),
+        bottom_margin_pos,
+    )
+    .unwrap();
+
+    ctx.restore();
+    trace_boundary(TraceAction::Exit);
+}
+
+// Frequencies of 1kHz and above are shown in kHz
+fn decade_label(freq: f64) -> String {
+    if freq >= 1000.0 {
+        format!("{}k", freq / 1000.0)
+    } else {
+        format!("{}", freq)
+    }
+}
+
 /***********************************************************************************************************************
  * Draw a key symbol
  */
@@ -797,10 +1122,13 @@ fn draw_curved_path(
 }
 
 /***********************************************************************************************************************
- * Translate an absorption value ranging from 0.00 .. 0.99 to a canvas pixel location
+ * Horizontal space available for a device diagram between the wall and the Y axis name
  */
-fn scaled_y_pos(start: f64, axis_length: f64) -> impl Fn(f64) -> f64 {
-    move |this_y: f64| start - (this_y * axis_length)
+fn diagram_width(tick_label_width: f64, y_axis_inset: &f64) -> f64 {
+    y_axis_name_x_pos(tick_label_width, y_axis_inset)
+        - render::constants::LEFT_MARGIN_INSET
+        - render::constants::WALL_IMG_WIDTH
+        - crate::chart::constants::LABEL_FONT_SIZE
 }
 
 /***********************************************************************************************************************
@@ -8,10 +8,12 @@ mod canvas_utils;
 pub mod constants;
 pub mod draw;
 
-use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen::JsValue;
 
 use super::{constants::*, render::constants::*};
-use crate::config::chart::{ChartConfig, ChartInfo, SeriesData};
+use crate::config::chart::{
+    ChartConfig, ChartInfo, MeasuredOverlay, SeriesBand, SeriesData, SeriesDeviation, SeriesMetadata,
+};
 
 /***********************************************************************************************************************
  * Trace functionality
@@ -27,7 +29,7 @@ pub const MOD_NAME: &str = "chart::render";
  */
 pub fn generic_device<'a>(
     device_info: GenericDeviceInfo<'a>,
-    chart_cfg: &ChartConfig,
+    chart_cfg: &'a ChartConfig,
     chart_title: &str,
 ) -> ChartInfo<'a> {
     const FN_NAME: &str = "generic_device";
@@ -36,9 +38,7 @@ pub fn generic_device<'a>(
 
     trace_boundary(TraceAction::Enter);
 
-    let document = web_sys::window().unwrap().document().unwrap();
-    let canvas_el = document.get_element_by_id(GRAPH_CANVAS_ID).unwrap();
-    let canvas = canvas_el.dyn_into::<web_sys::HtmlCanvasElement>().unwrap();
+    let canvas = canvas_utils::graph_canvas();
 
     let y_axis_inset: f64 = if chart_cfg.show_diagram {
         Y_AXIS_INSET_DIAGRAM
@@ -46,19 +46,32 @@ pub fn generic_device<'a>(
         Y_AXIS_INSET_NO_DIAGRAM
     };
 
-    let (_, _, _, x_axis_length, y_axis_length) = canvas_utils::canvas_dimensions(&canvas, &y_axis_inset);
+    let (_, _, _, _, y_axis_length) = canvas_utils::canvas_dimensions(&canvas, &y_axis_inset);
 
     canvas_utils::clear(&canvas);
 
     // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
     // Each device type has one or more plot series, each defined by a relevant metadata object
-    let series_metadata = match device_info.device_type {
+    let mut series_metadata = match device_info.device_type {
         DeviceType::RigidBackedPorousAbsorber => vec![&METADATA_NO_AIR_GAP, &METADATA_AIR_GAP],
         DeviceType::PerforatedPanelAbsorber => vec![&METADATA_NO_AIR_GAP, &METADATA_ABS_PANEL, &METADATA_ABS_BACKING],
         DeviceType::SlottedPanelAbsorber => vec![&METADATA_NO_AIR_GAP, &METADATA_ABS_PANEL, &METADATA_ABS_BACKING],
         DeviceType::MicroperforatedPanelAbsorber => vec![&METADATA_MP_PANEL],
     };
 
+    // Any measured datasets are also listed in the key
+    let measured_metadata: Vec<SeriesMetadata> = chart_cfg
+        .measured_data
+        .iter()
+        .enumerate()
+        .map(|(idx, measured)| SeriesMetadata {
+            name: &measured.label,
+            plot_colour: MEASURED_DATA_COLOURS[idx % MEASURED_DATA_COLOURS.len()],
+        })
+        .collect();
+
+    series_metadata.extend(measured_metadata.iter());
+
     let (chart_box, widest_y_tick_label) = draw::axes(&canvas, &chart_cfg, &y_axis_inset);
 
     draw::title_and_key(
@@ -74,6 +87,9 @@ pub fn generic_device<'a>(
         draw::device_diagram(&device_info, widest_y_tick_label, &y_axis_length, &Y_AXIS_INSET_DIAGRAM);
     }
 
+    // Series outside the view limits must not be drawn over the axes
+    draw::clip_to_chart_box(&canvas, &chart_box);
+
     // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
     // Define series data
     //
@@ -91,9 +107,7 @@ pub fn generic_device<'a>(
                     device_info.abs_series[0].plot_points.to_vec(),
                     &JsValue::from(METADATA_AIR_GAP.plot_colour),
                     &chart_cfg.smooth_curve,
-                    &x_axis_length,
-                    &y_axis_length,
-                    &y_axis_inset,
+                    &chart_box,
                 ),
             },
             SeriesData {
@@ -103,9 +117,7 @@ pub fn generic_device<'a>(
                     device_info.abs_series[1].plot_points.to_vec(),
                     &JsValue::from(METADATA_NO_AIR_GAP.plot_colour),
                     &chart_cfg.smooth_curve,
-                    &x_axis_length,
-                    &y_axis_length,
-                    &y_axis_inset,
+                    &chart_box,
                 ),
             },
         ],
@@ -120,9 +132,7 @@ pub fn generic_device<'a>(
                     device_info.abs_series[0].plot_points.to_vec(),
                     &JsValue::from(METADATA_NO_AIR_GAP.plot_colour),
                     &chart_cfg.smooth_curve,
-                    &x_axis_length,
-                    &y_axis_length,
-                    &y_axis_inset,
+                    &chart_box,
                 ),
             },
             SeriesData {
@@ -132,9 +142,7 @@ pub fn generic_device<'a>(
                     device_info.abs_series[1].plot_points.to_vec(),
                     &JsValue::from(METADATA_ABS_PANEL.plot_colour),
                     &chart_cfg.smooth_curve,
-                    &x_axis_length,
-                    &y_axis_length,
-                    &y_axis_inset,
+                    &chart_box,
                 ),
             },
             SeriesData {
@@ -144,9 +152,7 @@ pub fn generic_device<'a>(
                     device_info.abs_series[2].plot_points.to_vec(),
                     &JsValue::from(METADATA_ABS_BACKING.plot_colour),
                     &chart_cfg.smooth_curve,
-                    &x_axis_length,
-                    &y_axis_length,
-                    &y_axis_inset,
+                    &chart_box,
                 ),
             },
         ],
@@ -161,9 +167,7 @@ pub fn generic_device<'a>(
                     device_info.abs_series[0].plot_points.to_vec(),
                     &JsValue::from(METADATA_NO_AIR_GAP.plot_colour),
                     &chart_cfg.smooth_curve,
-                    &x_axis_length,
-                    &y_axis_length,
-                    &y_axis_inset,
+                    &chart_box,
                 ),
             },
             SeriesData {
@@ -173,9 +177,7 @@ pub fn generic_device<'a>(
                     device_info.abs_series[1].plot_points.to_vec(),
                     &JsValue::from(METADATA_ABS_PANEL.plot_colour),
                     &chart_cfg.smooth_curve,
-                    &x_axis_length,
-                    &y_axis_length,
-                    &y_axis_inset,
+                    &chart_box,
                 ),
             },
             SeriesData {
@@ -185,9 +187,7 @@ pub fn generic_device<'a>(
                     device_info.abs_series[2].plot_points.to_vec(),
                     &JsValue::from(METADATA_ABS_BACKING.plot_colour),
                     &chart_cfg.smooth_curve,
-                    &x_axis_length,
-                    &y_axis_length,
-                    &y_axis_inset,
+                    &chart_box,
                 ),
             },
         ],
@@ -201,13 +201,201 @@ pub fn generic_device<'a>(
                 device_info.abs_series[0].plot_points.to_vec(),
                 &JsValue::from(METADATA_MP_PANEL.plot_colour),
                 &chart_cfg.smooth_curve,
-                &x_axis_length,
-                &y_axis_length,
-                &y_axis_inset,
+                &chart_box,
             ),
         }],
     };
 
+    // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+    // Overlay any measured datasets, and compare them with the unrounded values of each calculated series
+    let measured_data: Vec<MeasuredOverlay> = chart_cfg
+        .measured_data
+        .iter()
+        .zip(measured_metadata.iter())
+        .map(|(measured, metadata)| MeasuredOverlay {
+            label: &measured.label,
+            plot_points: draw::measured_points(
+                &canvas,
+                &chart_cfg.frequencies,
+                &chart_cfg
+                    .frequencies
+                    .iter()
+                    .map(|f| measured.interpolate(*f))
+                    .collect::<Vec<Option<f64>>>(),
+                &JsValue::from(metadata.plot_colour),
+                &chart_box,
+            ),
+            deviations: device_info
+                .abs_series
+                .iter()
+                .map(|series| SeriesDeviation {
+                    name: series.name,
+                    rms_deviation: measured.rms_deviation(&series.plot_points),
+                })
+                .collect(),
+        })
+        .collect();
+
+    draw::remove_clip(&canvas);
+
     trace_boundary(TraceAction::Exit);
-    ChartInfo { chart_box, series_data }
+    ChartInfo {
+        chart_box,
+        series_data,
+        measured_data,
+        derived_values: vec![],
+        warnings: vec![],
+        explanation: None,
+    }
+}
+
+/***********************************************************************************************************************
+ * A device included in a comparison, together with the indices of the series to be plotted
+ */
+pub struct ComparedDevice<'a> {
+    pub label: &'a str,
+    pub device_info: GenericDeviceInfo<'a>,
+    pub series: Vec<usize>,
+}
+
+/***********************************************************************************************************************
+ * Plot the selected series of several devices, possibly of different types, on a shared chart
+ *
+ * The series metadata supplies the key entry of each selected series in the same order as the devices and their series
+ * indices
+ */
+pub fn comparison<'a>(
+    devices: &[ComparedDevice<'a>],
+    series_metadata: &[SeriesMetadata<'a>],
+    chart_cfg: &'a ChartConfig,
+    chart_title: &str,
+) -> ChartInfo<'a> {
+    const FN_NAME: &str = "comparison";
+
+    let trace_boundary = make_boundary_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, FN_NAME);
+
+    trace_boundary(TraceAction::Enter);
+
+    let canvas = canvas_utils::graph_canvas();
+
+    let y_axis_inset: f64 = if chart_cfg.show_diagram {
+        Y_AXIS_INSET_DIAGRAM
+    } else {
+        Y_AXIS_INSET_NO_DIAGRAM
+    };
+
+    let (_, _, _, _, y_axis_length) = canvas_utils::canvas_dimensions(&canvas, &y_axis_inset);
+
+    canvas_utils::clear(&canvas);
+
+    let (chart_box, widest_y_tick_label) = draw::axes(&canvas, chart_cfg, &y_axis_inset);
+
+    draw::title_and_key(
+        &canvas,
+        chart_title,
+        &FONT_METADATA_TITLE,
+        &FONT_METADATA_LABEL,
+        series_metadata.iter().collect(),
+    );
+
+    // Each device is shown as a thumbnail diagram
+    if chart_cfg.show_diagram {
+        draw::device_thumbnails(
+            &devices
+                .iter()
+                .map(|device| (device.label, &device.device_info))
+                .collect::<Vec<(&str, &GenericDeviceInfo)>>(),
+            widest_y_tick_label,
+            &y_axis_length,
+            &Y_AXIS_INSET_DIAGRAM,
+        );
+    }
+
+    draw::clip_to_chart_box(&canvas, &chart_box);
+
+    let series_data: Vec<SeriesData> = devices
+        .iter()
+        .flat_map(|device| {
+            device
+                .series
+                .iter()
+                .map(move |series_idx| &device.device_info.abs_series[*series_idx])
+        })
+        .zip(series_metadata.iter())
+        .map(|(series, metadata)| SeriesData {
+            name: metadata.name,
+            plot_points: draw::splines(
+                &canvas,
+                series.plot_points.to_vec(),
+                &JsValue::from(metadata.plot_colour),
+                &chart_cfg.smooth_curve,
+                &chart_box,
+            ),
+        })
+        .collect();
+
+    draw::remove_clip(&canvas);
+
+    trace_boundary(TraceAction::Exit);
+    ChartInfo {
+        chart_box,
+        series_data,
+        measured_data: vec![],
+        derived_values: vec![],
+        warnings: vec![],
+        explanation: None,
+    }
+}
+
+/***********************************************************************************************************************
+ * Plot a chart for a generic device, then shade the uncertainty envelope around each series
+ */
+pub fn generic_device_with_bands<'a>(
+    device_info: GenericDeviceInfo<'a>,
+    bands: &[SeriesBand],
+    chart_cfg: &'a ChartConfig,
+    chart_title: &str,
+) -> ChartInfo<'a> {
+    const FN_NAME: &str = "generic_device_with_bands";
+
+    let trace_boundary = make_boundary_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, FN_NAME);
+
+    trace_boundary(TraceAction::Enter);
+
+    let plot_colours = series_plot_colours(device_info.device_type);
+    let chart_info = generic_device(device_info, chart_cfg, chart_title);
+
+    let canvas = canvas_utils::graph_canvas();
+    draw::clip_to_chart_box(&canvas, &chart_info.chart_box);
+
+    for (band, plot_colour) in bands.iter().zip(plot_colours) {
+        draw::envelope(
+            &canvas,
+            &band.band_points.iter().map(|bp| bp.freq).collect::<Vec<f64>>(),
+            &band.band_points.iter().map(|bp| bp.lower).collect::<Vec<f64>>(),
+            &band.band_points.iter().map(|bp| bp.upper).collect::<Vec<f64>>(),
+            &JsValue::from(plot_colour),
+            &chart_info.chart_box,
+        );
+    }
+
+    draw::remove_clip(&canvas);
+
+    trace_boundary(TraceAction::Exit);
+    chart_info
+}
+
+/***********************************************************************************************************************
+ * The colour used to plot each of a device's series, in the order in which the calc_engine modules generate them
+ */
+fn series_plot_colours(device_type: DeviceType) -> Vec<&'static str> {
+    match device_type {
+        DeviceType::RigidBackedPorousAbsorber => vec![METADATA_AIR_GAP.plot_colour, METADATA_NO_AIR_GAP.plot_colour],
+        DeviceType::PerforatedPanelAbsorber | DeviceType::SlottedPanelAbsorber => vec![
+            METADATA_NO_AIR_GAP.plot_colour,
+            METADATA_ABS_PANEL.plot_colour,
+            METADATA_ABS_BACKING.plot_colour,
+        ],
+        DeviceType::MicroperforatedPanelAbsorber => vec![METADATA_MP_PANEL.plot_colour],
+    }
 }
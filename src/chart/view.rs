@@ -0,0 +1,99 @@
+/***********************************************************************************************************************
+ * Porous Absorber Calculator - Chart zoom and pan
+ *
+ * JavaScript holds the current view limits (returned as part of the chart box) and asks for new limits in response to
+ * mouse wheel or drag events.  The new limits are then passed back in the device arguments when the chart is redrawn
+ *
+ * (c) Chris Whealy 2020, 2024
+ */
+use serde_derive::Deserialize;
+use wasm_bindgen::JsValue;
+
+use crate::{
+    config::{chart::ViewLimits, errors_to_js, ok_to_js, ErrorCode, GenericError},
+    trace::{trace_flags::trace_flag_for, *},
+};
+
+pub const MOD_NAME: &str = "chart::view";
+
+/***********************************************************************************************************************
+ * Values received from the client
+ *
+ * A zoom factor greater than 1 zooms in, and less than 1 zooms out.  Pan fractions are relative to the current width
+ * and height of the view; positive values move the view towards higher frequencies and absorption values
+ */
+#[derive(Debug, Deserialize)]
+pub struct ZoomArgs {
+    pub view: ViewLimits,
+    pub factor: f64,
+    pub centre_freq: f64,
+    pub centre_abs: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PanArgs {
+    pub view: ViewLimits,
+    pub x_fraction: f64,
+    pub y_fraction: f64,
+}
+
+/***********************************************************************************************************************
+ * Handle incoming arguments for zooming the chart view
+ */
+pub fn zoom(arg_obj: ZoomArgs) -> JsValue {
+    let trace_boundary = make_boundary_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, "zoom");
+    trace_boundary(TraceAction::Enter);
+
+    let new_view = arg_obj.view.validate().and_then(|view| {
+        if !(arg_obj.factor.is_finite() && arg_obj.factor > 0.0) {
+            Err(GenericError::new(
+                ErrorCode::OutOfRange,
+                format!("Zoom factor must be greater than zero, not '{:?}'", arg_obj.factor),
+            )
+            .for_field("factor")
+            .with_value(arg_obj.factor))
+        } else if !(arg_obj.centre_freq.is_finite() && arg_obj.centre_freq > 0.0 && arg_obj.centre_abs.is_finite()) {
+            Err(GenericError::new(
+                ErrorCode::InvalidValue,
+                format!("Invalid zoom centre '({:?}, {:?})'", arg_obj.centre_freq, arg_obj.centre_abs),
+            )
+            .for_field("centre_freq"))
+        } else {
+            Ok(view.zoom(arg_obj.factor, arg_obj.centre_freq, arg_obj.centre_abs))
+        }
+    });
+
+    trace_boundary(TraceAction::Exit);
+    view_result(new_view)
+}
+
+/***********************************************************************************************************************
+ * Handle incoming arguments for panning the chart view
+ */
+pub fn pan(arg_obj: PanArgs) -> JsValue {
+    let trace_boundary = make_boundary_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, "pan");
+    trace_boundary(TraceAction::Enter);
+
+    let new_view = arg_obj.view.validate().and_then(|view| {
+        if !(arg_obj.x_fraction.is_finite() && arg_obj.y_fraction.is_finite()) {
+            Err(GenericError::new(
+                ErrorCode::InvalidValue,
+                format!("Invalid pan distance '({:?}, {:?})'", arg_obj.x_fraction, arg_obj.y_fraction),
+            )
+            .for_field("x_fraction"))
+        } else {
+            Ok(view.pan(arg_obj.x_fraction, arg_obj.y_fraction))
+        }
+    });
+
+    trace_boundary(TraceAction::Exit);
+    view_result(new_view)
+}
+
+// Return either the new view limits or the error
+fn view_result(new_view: Result<ViewLimits, GenericError>) -> JsValue {
+    match new_view {
+        Ok(view) => ok_to_js(&view),
+        Err(err) => errors_to_js(&[err]),
+    }
+}
@@ -1,11 +1,15 @@
 /***********************************************************************************************************************
  * Porous Absorber Calculator - Air properties
  *
+ * The density, viscosity, thermal conductivity, specific heat ratio and Prandtl number of the air are calculated from
+ * the configured temperature, pressure and humidity, so that high temperature applications are modelled correctly
+ *
  * (c) Chris Whealy 2020, 2024
  */
-use super::GenericError;
+use super::{ErrorCode, GenericError};
+use libm::{exp, pow, sqrt};
 use crate::{
-    config::{constants, ranges::NamedRange},
+    config::{constants, ranges::NamedRange, units},
     utils::maths_functions::TAU,
 };
 use serde_derive::{Deserialize, Serialize};
@@ -15,51 +19,160 @@ use serde_derive::{Deserialize, Serialize};
  */
 // Gas constant (J/Kg.K)
 const GAS_CONSTANT: f64 = 287.05;
-// Specific heat ratio
-// const GAMMA: f64 = 1.402;
-// Air density at 0C (Kg.m^-3)
-// const AIR_DENSITY_0: f64 = 1.293;
+// Gas constant of water vapour (J/Kg.K)
+const GAS_CONSTANT_H2O: f64 = 461.495;
 const ONE_ATM: f64 = 101325.0; // One atmosphere (Pa)
-const KELVIN_OFFSET: f64 = 273.15; // Zero celsius in degrees Kelvin
+pub const KELVIN_OFFSET: f64 = 273.15; // Zero celsius in degrees Kelvin
+
+// Sutherland's law for the dynamic viscosity of dry air
+const SUTHERLAND_REF_VISCOSITY: f64 = 1.716e-5; // Pa.s at the reference temperature
+const SUTHERLAND_REF_TEMP: f64 = 273.15; // K
+const SUTHERLAND_CONSTANT: f64 = 110.4; // K
+
+// Sutherland's law for the thermal conductivity of dry air
+const SUTHERLAND_REF_CONDUCTIVITY: f64 = 0.0241; // W/m.K at the reference temperature
+const SUTHERLAND_CONSTANT_CONDUCTIVITY: f64 = 194.0; // K
+
+// Ratio of the molar masses of water vapour and dry air
+const MOLAR_MASS_RATIO_H2O: f64 = 0.622;
+// Specific heat capacity of water vapour at constant pressure (J/kg.K)
+const SPECIFIC_HEAT_CP_H2O: f64 = 1860.0;
+
+// ISO 9613-1 reference values
+const ISO_REF_TEMP: f64 = 293.15; // K
+const ISO_TRIPLE_POINT_TEMP: f64 = 273.16; // K
+
+/***********************************************************************************************************************
+ * Specific heat capacity of dry air at constant pressure (J/kg.K) from a quadratic fit valid between 250K and 1050K
+ */
+pub fn specific_heat_cp(temp: f64) -> f64 {
+    let abs_temp = temp + KELVIN_OFFSET;
+    1002.5 + 275.0e-6 * (abs_temp - 200.0) * (abs_temp - 200.0)
+}
+
+pub fn specific_heat_ratio(cp: f64, gas_constant: f64) -> f64 {
+    cp / (cp - gas_constant)
+}
+
+/***********************************************************************************************************************
+ * Thermal conductivity of dry air (W/m.K) from Sutherland's law
+ */
+pub fn thermal_conductivity(temp: f64) -> f64 {
+    let abs_temp = temp + KELVIN_OFFSET;
+
+    SUTHERLAND_REF_CONDUCTIVITY
+        * pow(abs_temp / SUTHERLAND_REF_TEMP, 1.5)
+        * (SUTHERLAND_REF_TEMP + SUTHERLAND_CONSTANT_CONDUCTIVITY)
+        / (abs_temp + SUTHERLAND_CONSTANT_CONDUCTIVITY)
+}
+
+/***********************************************************************************************************************
+ * Saturation vapour pressure of water (Pa) as given in ISO 9613-1 Annex B
+ */
+pub fn saturation_vapour_pressure(temp: f64) -> f64 {
+    let exponent = -6.8346 * pow(ISO_TRIPLE_POINT_TEMP / (temp + KELVIN_OFFSET), 1.261) + 4.6151;
+    ONE_ATM * pow(10.0, exponent)
+}
 
-pub const AIR_VISCOSITY: f64 = 0.0000185; // Kinemetric viscosity of air (m^2/s)
+/***********************************************************************************************************************
+ * Moist air is treated as a mixture of dry air and water vapour at the partial pressure given by the relative humidity
+ */
+pub fn humid_air_density(pressure: f64, temp: f64, relative_humidity: f64) -> f64 {
+    let abs_temp = temp + KELVIN_OFFSET;
+    let vapour_pressure = vapour_pressure(temp, relative_humidity);
+
+    (pressure * ONE_ATM - vapour_pressure) / (GAS_CONSTANT * abs_temp) + vapour_pressure / (GAS_CONSTANT_H2O * abs_temp)
+}
 
-pub fn air_density(pressure: f64, temp: i16) -> f64 {
-    (pressure * ONE_ATM) / (GAS_CONSTANT * (temp as f64 + KELVIN_OFFSET))
+// Water vapour is lighter than dry air, so the gas constant of moist air is higher
+pub fn humid_gas_constant(pressure: f64, temp: f64, relative_humidity: f64) -> f64 {
+    let vapour_fraction = vapour_pressure(temp, relative_humidity) / (pressure * ONE_ATM);
+    GAS_CONSTANT / (1.0 - vapour_fraction * (1.0 - MOLAR_MASS_RATIO_H2O))
 }
 
-pub fn sound_velocity(temp: f64) -> f64 {
-    // ((GAMMA * ONE_ATM) / AIR_DENSITY_0).sqrt() = 331.4614688
-    331.4614688 * (1.0 + (temp / KELVIN_OFFSET)).sqrt()
+// The specific heat capacities of dry air and water vapour are weighted by their mass fractions
+pub fn humid_specific_heat_cp(pressure: f64, temp: f64, relative_humidity: f64) -> f64 {
+    let vapour_fraction = vapour_pressure(temp, relative_humidity) / (pressure * ONE_ATM);
+    let mass_fraction = MOLAR_MASS_RATIO_H2O * vapour_fraction / (1.0 - vapour_fraction * (1.0 - MOLAR_MASS_RATIO_H2O));
+
+    (1.0 - mass_fraction) * specific_heat_cp(temp) + mass_fraction * SPECIFIC_HEAT_CP_H2O
 }
 
 /***********************************************************************************************************************
- * Air pressure and temperature range check values
+ * Dynamic viscosity (Pa.s)
+ *
+ * Dry air follows Sutherland's law, and the viscosity of moist air is obtained from Wilke's mixing rule
  */
-const TEMP_RANGE: NamedRange<i16> = NamedRange {
+pub fn dynamic_viscosity(pressure: f64, temp: f64, relative_humidity: f64) -> f64 {
+    let abs_temp = temp + KELVIN_OFFSET;
+    let mu_air = SUTHERLAND_REF_VISCOSITY
+        * pow(abs_temp / SUTHERLAND_REF_TEMP, 1.5)
+        * (SUTHERLAND_REF_TEMP + SUTHERLAND_CONSTANT)
+        / (abs_temp + SUTHERLAND_CONSTANT);
+
+    // Linear fit to the viscosity of water vapour between 0C and 100C, extrapolated to higher temperatures
+    let mu_h2o = 8.85e-6 + 3.45e-8 * temp;
+    let x_h2o = vapour_pressure(temp, relative_humidity) / (pressure * ONE_ATM);
+    let x_air = 1.0 - x_h2o;
+
+    let phi = |mu_i: f64, mu_j: f64, m_i: f64, m_j: f64| {
+        let numerator = 1.0 + sqrt(mu_i / mu_j) * pow(m_j / m_i, 0.25);
+        numerator * numerator / sqrt(8.0 * (1.0 + m_i / m_j))
+    };
+
+    let phi_air_h2o = phi(mu_air, mu_h2o, 1.0, MOLAR_MASS_RATIO_H2O);
+    let phi_h2o_air = phi(mu_h2o, mu_air, MOLAR_MASS_RATIO_H2O, 1.0);
+
+    x_air * mu_air / (x_air + x_h2o * phi_air_h2o) + x_h2o * mu_h2o / (x_h2o + x_air * phi_h2o_air)
+}
+
+/***********************************************************************************************************************
+ * Air pressure, temperature and humidity range check values
+ *
+ * The upper temperature limit allows for industrial applications, and lies within the range of the specific heat fit
+ */
+const TEMP_RANGE: NamedRange<f64> = NamedRange {
     name: constants::TXT_AIR_TEMP,
+    field: "air_temp",
     units: constants::UNITS_TEMP,
-    min: -20,
-    default: 20,
-    max: 100,
+    min: -20.0,
+    default: 20.0,
+    max: 500.0,
 };
 
 const PRESSURE_RANGE: NamedRange<f64> = NamedRange {
     name: constants::TXT_AIR_PRESSURE,
+    field: "air_pressure",
     units: constants::UNITS_PRESSURE,
     min: 0.8,
     default: 1.0,
     max: 1.1,
 };
 
+// Air is assumed to be dry unless a relative humidity is given
+const HUMIDITY_RANGE: NamedRange<f64> = NamedRange {
+    name: constants::TXT_RELATIVE_HUMIDITY,
+    field: "relative_humidity",
+    units: constants::UNITS_PERCENT,
+    min: 0.0,
+    default: 0.0,
+    max: 100.0,
+};
+
 /***********************************************************************************************************************
  * Air properties
  */
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AirConfig {
-    pub temperature: i16,
+    pub temperature: f64,
     pub pressure: f64,
+    pub relative_humidity: f64,
     pub density: f64,
+    pub dynamic_viscosity: f64,
+    pub kinematic_viscosity: f64,
+    pub thermal_conductivity: f64,
+    pub specific_heat_ratio: f64,
+    pub prandtl_number: f64,
     pub velocity: f64,
     pub impedance: f64,
     pub tau_over_c: f64,
@@ -69,30 +182,194 @@ pub struct AirConfig {
 
 impl AirConfig {
     pub fn default() -> AirConfig {
-        AirConfig::new(TEMP_RANGE.default, PRESSURE_RANGE.default).unwrap()
+        AirConfig::new(TEMP_RANGE.default, PRESSURE_RANGE.default, HUMIDITY_RANGE.default).unwrap()
     }
 
-    pub fn new(temp_arg: i16, pressure_arg: f64) -> Result<AirConfig, GenericError> {
+    pub fn new(temp_arg: f64, pressure_arg: f64, humidity_arg: f64) -> Result<AirConfig, GenericError> {
         if !TEMP_RANGE.contains(temp_arg) {
-            return Err(GenericError::new_from_i16(TEMP_RANGE, temp_arg));
+            return Err(GenericError::new_from_f64(TEMP_RANGE, temp_arg));
         }
 
         if !PRESSURE_RANGE.contains(pressure_arg) {
             return Err(GenericError::new_from_f64(PRESSURE_RANGE, pressure_arg));
         }
 
-        let den = air_density(pressure_arg, temp_arg);
-        let vel = sound_velocity(temp_arg as f64);
+        if !HUMIDITY_RANGE.contains(humidity_arg) {
+            return Err(GenericError::new_from_f64(HUMIDITY_RANGE, humidity_arg));
+        }
+
+        // Above 100C, a high relative humidity would require water vapour at more than the air pressure
+        let saturation_pressure = saturation_vapour_pressure(temp_arg);
+
+        if vapour_pressure(temp_arg, humidity_arg) >= pressure_arg * ONE_ATM {
+            return Err(GenericError::new(
+                ErrorCode::Inconsistent,
+                format!(
+                    "Relative humidity of {}% at an air temperature of {} requires a water vapour pressure greater \
+                     than the air pressure of {}",
+                    humidity_arg,
+                    units::quote(TEMP_RANGE.units, temp_arg),
+                    units::quote(PRESSURE_RANGE.units, pressure_arg)
+                ),
+            )
+            .for_field(HUMIDITY_RANGE.field)
+            .with_value(humidity_arg)
+            .with_limits(HUMIDITY_RANGE.min, 100.0 * pressure_arg * ONE_ATM / saturation_pressure));
+        }
+
+        let den = humid_air_density(pressure_arg, temp_arg, humidity_arg);
+        let gas_constant = humid_gas_constant(pressure_arg, temp_arg, humidity_arg);
+        let cp = humid_specific_heat_cp(pressure_arg, temp_arg, humidity_arg);
+        let gamma = specific_heat_ratio(cp, gas_constant);
+        let vel = sqrt(gamma * gas_constant * (temp_arg + KELVIN_OFFSET));
+        let viscosity = dynamic_viscosity(pressure_arg, temp_arg, humidity_arg);
+        let conductivity = thermal_conductivity(temp_arg);
 
         Ok(AirConfig {
             temperature: temp_arg,
             pressure: pressure_arg,
+            relative_humidity: humidity_arg,
             density: den,
+            dynamic_viscosity: viscosity,
+            kinematic_viscosity: viscosity / den,
+            thermal_conductivity: conductivity,
+            specific_heat_ratio: gamma,
+            prandtl_number: viscosity * cp / conductivity,
             velocity: vel,
             impedance: vel * den,
             tau_over_c: TAU / vel,
             c_over_tau: vel / TAU,
-            density_over_viscosity: den / AIR_VISCOSITY,
+            density_over_viscosity: den / viscosity,
         })
     }
+
+    /*******************************************************************************************************************
+     * Atmospheric absorption attenuation coefficient (dB/m) at the given frequency as defined in ISO 9613-1
+     */
+    pub fn attenuation_coefficient(&self, frequency: f64) -> f64 {
+        let abs_temp = self.temperature + KELVIN_OFFSET;
+        let temp_ratio = abs_temp / ISO_REF_TEMP;
+        let pressure_ratio = self.pressure;
+
+        // Molar concentration of water vapour (%)
+        let h = self.relative_humidity * saturation_vapour_pressure(self.temperature) / (pressure_ratio * ONE_ATM);
+
+        // Relaxation frequencies of oxygen and nitrogen
+        let fr_o = pressure_ratio * (24.0 + 4.04e4 * h * (0.02 + h) / (0.391 + h));
+        let fr_n = pressure_ratio
+            * pow(temp_ratio, -0.5)
+            * (9.0 + 280.0 * h * exp(-4.170 * (pow(temp_ratio, -1.0 / 3.0) - 1.0)));
+
+        let f_sqrd = frequency * frequency;
+
+        8.686
+            * f_sqrd
+            * (1.84e-11 / pressure_ratio * sqrt(temp_ratio)
+                + pow(temp_ratio, -2.5)
+                    * (0.01275 * exp(-2239.1 / abs_temp) / (fr_o + f_sqrd / fr_o)
+                        + 0.1068 * exp(-3352.0 / abs_temp) / (fr_n + f_sqrd / fr_n)))
+    }
+}
+
+// Partial pressure of water vapour (Pa)
+fn vapour_pressure(temp: f64, relative_humidity: f64) -> f64 {
+    relative_humidity / 100.0 * saturation_vapour_pressure(temp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "expected {} ± {}, got {}",
+            expected,
+            tolerance,
+            actual
+        );
+    }
+
+    #[test]
+    fn thermal_properties_of_dry_air() {
+        let air = AirConfig::new(20.0, 1.0, 0.0).unwrap();
+
+        assert_close(air.thermal_conductivity, 0.0257, 0.0002);
+        assert_close(air.specific_heat_ratio, 1.400, 0.002);
+        assert_close(air.prandtl_number, 0.71, 0.005);
+    }
+
+    #[test]
+    fn thermal_properties_follow_temperature() {
+        let cold = AirConfig::new(20.0, 1.0, 0.0).unwrap();
+        let hot = AirConfig::new(500.0, 1.0, 0.0).unwrap();
+
+        // Published values for dry air at 500C are about 0.056 W/m.K, 1.357 and 0.70
+        assert_close(hot.thermal_conductivity, 0.056, 0.002);
+        assert_close(hot.specific_heat_ratio, 1.357, 0.005);
+        assert_close(hot.prandtl_number, 0.70, 0.02);
+        assert!(hot.dynamic_viscosity > cold.dynamic_viscosity);
+    }
+
+    #[test]
+    fn humidity_lowers_specific_heat_ratio_and_raises_speed_of_sound() {
+        let dry = AirConfig::new(20.0, 1.0, 0.0).unwrap();
+        let humid = AirConfig::new(20.0, 1.0, 100.0).unwrap();
+
+        assert!(humid.specific_heat_ratio < dry.specific_heat_ratio);
+        assert!(humid.velocity > dry.velocity);
+    }
+
+    #[test]
+    fn dry_air_at_20c_matches_published_values() {
+        let air = AirConfig::new(20.0, 1.0, 0.0).unwrap();
+
+        assert_close(air.velocity, 343.2, 0.2);
+        assert_close(air.density, 1.204, 0.001);
+        assert_close(air.dynamic_viscosity, 1.81e-5, 0.01e-5);
+        assert_close(air.kinematic_viscosity, 1.50e-5, 0.01e-5);
+    }
+
+    #[test]
+    fn humidity_lowers_density_and_viscosity() {
+        let dry = AirConfig::new(20.0, 1.0, 0.0).unwrap();
+        let humid = AirConfig::new(20.0, 1.0, 50.0).unwrap();
+
+        // Water vapour at 20C and 50%RH has a partial pressure of about 1170 Pa
+        assert_close(saturation_vapour_pressure(20.0), 2339.0, 5.0);
+        assert_close(humid.density, 1.198, 0.001);
+        assert!(humid.dynamic_viscosity < dry.dynamic_viscosity);
+        assert_close(humid.velocity, 344.0, 0.2);
+        assert_close(humid.specific_heat_ratio, 1.399, 0.001);
+    }
+
+    #[test]
+    fn attenuation_matches_iso_9613_1() {
+        // ISO 9613-1 Table 1 at 20C and 50%RH
+        let air = AirConfig::new(20.0, 1.0, 50.0).unwrap();
+        assert_close(air.attenuation_coefficient(1000.0), 0.00466, 0.0002);
+
+        // ISO 9613-2 Table 2 at 20C and 70%RH, given to 0.1 dB/km
+        let air = AirConfig::new(20.0, 1.0, 70.0).unwrap();
+        let octave_bands = [
+            (63.0, 0.0001),
+            (125.0, 0.0003),
+            (250.0, 0.0011),
+            (500.0, 0.0028),
+            (1000.0, 0.0050),
+            (2000.0, 0.0090),
+            (4000.0, 0.0229),
+            (8000.0, 0.0766),
+        ];
+
+        for (frequency, expected) in octave_bands {
+            assert_close(air.attenuation_coefficient(frequency), expected, 0.00005 + expected * 0.02);
+        }
+    }
+
+    #[test]
+    fn rejects_humidity_requiring_vapour_above_air_pressure() {
+        assert!(AirConfig::new(150.0, 1.0, 100.0).is_err());
+        assert!(AirConfig::new(150.0, 1.0, 10.0).is_ok());
+    }
 }
@@ -12,6 +12,7 @@ use crate::config::{constants, ranges::NamedRange};
  */
 const THICKNESS_RANGE: NamedRange<u16> = NamedRange {
     name: constants::TXT_AIR_GAP,
+    field: "air_gap_mm",
     units: constants::UNITS_THICKNESS,
     min: 0,
     default: 100,
@@ -3,43 +3,93 @@
  *
  * (c) Chris Whealy 2020, 2024
  */
-use libm::{fabs, log2, pow};
-use serde_derive::Serialize;
+use libm::{fabs, log10, log2, pow};
+use serde_derive::{Deserialize, Serialize};
 use std::fmt;
 
 use super::GenericError;
 use crate::config::{constants, ranges::NamedRange};
+use crate::trace::explain::Explanation;
 
 /***********************************************************************************************************************
- * Graph start frequency and octave subdivision range check values
+ * Graph frequency and octave subdivision range check values
  */
 pub const FREQ_RANGE: NamedRange<f64> = NamedRange {
     name: constants::TXT_FREQ_RANGE,
+    field: "graph_start_freq",
     units: constants::UNITS_FREQ,
-    min: 20.0,
+    min: 10.0,
     default: 62.5,
-    max: 100.0,
+    max: 20000.0,
 };
 
-const SUBDIVISIONS: [u16; 4] = [1, 2, 3, 6];
+pub const SUBDIVISIONS: [u16; 6] = [1, 2, 3, 6, 12, 24];
 const DEFAULT_SUBDIVISION: u16 = 3;
 
+// When no end frequency is given, the chart covers this many octaves above the start frequency
 const DISPLAY_OCTAVES: u16 = 8;
 
+// The number of frequencies either generated or supplied explicitly
+pub const MIN_FREQUENCIES: usize = 3;
+pub const MAX_FREQUENCIES: usize = 1000;
+
+// Band averaged absorption is calculated from this many samples evenly spaced in log frequency across each band
+pub const SAMPLES_PER_BAND: usize = 16;
+
+// ISO 3 R80 preferred numbers from which the ISO 266 nominal band centre frequencies of every subdivision are taken
+const R80_PREFERRED_NUMBERS: [f64; 80] = [
+    1.00, 1.03, 1.06, 1.09, 1.12, 1.15, 1.18, 1.22, 1.25, 1.28, 1.32, 1.36, 1.40, 1.45, 1.50, 1.55, 1.60, 1.65, 1.70,
+    1.75, 1.80, 1.85, 1.90, 1.95, 2.00, 2.06, 2.12, 2.18, 2.24, 2.30, 2.36, 2.43, 2.50, 2.58, 2.65, 2.72, 2.80, 2.90,
+    3.00, 3.07, 3.15, 3.25, 3.35, 3.45, 3.55, 3.65, 3.75, 3.87, 4.00, 4.12, 4.25, 4.37, 4.50, 4.62, 4.75, 4.87, 5.00,
+    5.15, 5.30, 5.45, 5.60, 5.80, 6.00, 6.15, 6.30, 6.50, 6.70, 6.90, 7.10, 7.30, 7.50, 7.75, 8.00, 8.25, 8.50, 8.75,
+    9.00, 9.25, 9.50, 9.75,
+];
+
+// ISO 266 band centres are referenced to 1kHz
+const ISO_REF_FREQ: f64 = 1000.0;
+
 /***********************************************************************************************************************
  * Possible errors when creating a chart
  */
 pub enum ErrType {
     Graph,
+    GraphEnd,
     Subdivision,
+    FrequencyCount,
+    FrequencyValue,
+    BandAverage,
+    View,
 }
 
 /***********************************************************************************************************************
- * Generate the required frequencies from the number of display octaves and the subdivisions
- * The range upper bound must be included in order not to omit the 16KHz value
+ * Band centre frequencies are either the ISO 266 exact base-10 values, or successive powers of two from the start
+ * frequency
  */
-fn gen_frequencies(graph_start_freq: &f64, subdivisions: &u16) -> Vec<f64> {
-    let intervals: Vec<u16> = (0..=(DISPLAY_OCTAVES * subdivisions)).collect();
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BandScale {
+    #[default]
+    Iso266,
+    BaseTwo,
+}
+
+impl BandScale {
+    // Ratio between adjacent band centre frequencies
+    fn band_ratio(&self, subdivisions: u16) -> f64 {
+        match self {
+            BandScale::Iso266 => pow(10.0, 0.3 / subdivisions as f64),
+            BandScale::BaseTwo => pow(2.0, 1.0 / subdivisions as f64),
+        }
+    }
+}
+
+/***********************************************************************************************************************
+ * Generate the required frequencies between the start and end frequencies at the given octave subdivisions
+ * A small tolerance is allowed so that rounding errors do not omit an end frequency that lies on an interval boundary
+ */
+fn gen_frequencies(graph_start_freq: &f64, graph_end_freq: &f64, subdivisions: &u16) -> Vec<f64> {
+    let interval_count = (log2(graph_end_freq / graph_start_freq) * *subdivisions as f64 + 1.0e-9).floor() as u32;
+    let intervals: Vec<u32> = (0..=interval_count).collect();
 
     intervals.iter().fold(vec![], |mut acc, interval_no| {
         acc.push(if interval_no == &0 {
@@ -52,43 +102,401 @@ fn gen_frequencies(graph_start_freq: &f64, subdivisions: &u16) -> Vec<f64> {
     })
 }
 
+/***********************************************************************************************************************
+ * ISO 266 band numbers count bands of width 1/subdivisions octave either side of 1kHz
+ * The start and end frequencies are moved to the nearest band centre, unless that centre lies outside FREQ_RANGE, in
+ * which case the next band inside the range is used
+ */
+fn iso_band_number(freq: f64, subdivisions: u16) -> i32 {
+    (log10(freq / ISO_REF_FREQ) * 10.0 * subdivisions as f64 / 3.0).round() as i32
+}
+
+fn gen_iso_band_numbers(graph_start_freq: &f64, graph_end_freq: &f64, subdivisions: &u16) -> Vec<i32> {
+    (iso_band_number(*graph_start_freq, *subdivisions)..=iso_band_number(*graph_end_freq, *subdivisions))
+        .filter(|band_no| FREQ_RANGE.contains(iso_exact_frequency(*band_no, *subdivisions)))
+        .collect()
+}
+
+fn iso_exact_frequency(band_no: i32, subdivisions: u16) -> f64 {
+    ISO_REF_FREQ * pow(10.0, 0.3 * band_no as f64 / subdivisions as f64)
+}
+
+// Each band of a 1/b octave subdivision is 24/b steps along the R80 series
+fn iso_nominal_frequency(band_no: i32, subdivisions: u16) -> f64 {
+    let r80_idx = band_no * 24 / subdivisions as i32;
+
+    R80_PREFERRED_NUMBERS[r80_idx.rem_euclid(80) as usize] * pow(10.0, 3.0 + r80_idx.div_euclid(80) as f64)
+}
+
+/***********************************************************************************************************************
+ * Frequencies below 100Hz are shown to 1dp unless they are whole numbers
+ */
+pub fn frequency_label(freq: f64) -> String {
+    if freq < 100.0 && (freq - freq.round()).abs() >= 0.05 {
+        format!("{:.1}", freq)
+    } else {
+        format!("{}", freq.round() as u32)
+    }
+}
+
+/***********************************************************************************************************************
+ * When calculating band averages, each band centre frequency is replaced by SAMPLES_PER_BAND frequencies spread
+ * evenly across the band on a log scale
+ */
+fn gen_band_samples(frequencies: &[f64], band_ratio: f64) -> Vec<f64> {
+    frequencies
+        .iter()
+        .flat_map(|centre| {
+            let lower_edge = centre / band_ratio.sqrt();
+
+            (0..SAMPLES_PER_BAND)
+                .map(move |idx| lower_edge * pow(band_ratio, (idx as f64 + 0.5) / SAMPLES_PER_BAND as f64))
+        })
+        .collect()
+}
+
+/***********************************************************************************************************************
+ * An explicit list of frequencies is sorted into ascending order and any duplicates are removed
+ */
+fn explicit_frequencies(frequency_list: &[f64]) -> Result<Vec<f64>, GenericError> {
+    if let Some(bad_freq) = frequency_list.iter().find(|f| !FREQ_RANGE.contains(**f)) {
+        return Err(GenericError::new_chart_err(ErrType::FrequencyValue, *bad_freq));
+    }
+
+    let mut frequencies = frequency_list.to_vec();
+    frequencies.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    frequencies.dedup();
+
+    Ok(frequencies)
+}
+
+/***********************************************************************************************************************
+ * Measured absorption data supplied by the client for overlaying on the chart
+ *
+ * The values can be supplied either as a list of points, or as CSV text in which each line starts with a frequency
+ * followed by an absorption value.  Lines that do not start with two numbers (such as a header row) are ignored
+ */
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MeasuredPoint {
+    pub freq: f64,
+    pub abs: f64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct MeasuredDataset {
+    #[serde(default)]
+    pub label: String,
+    #[serde(default)]
+    pub points: Vec<MeasuredPoint>,
+    #[serde(default)]
+    pub csv: String,
+}
+
+fn parse_csv(csv: &str) -> Vec<MeasuredPoint> {
+    csv.lines()
+        .filter_map(|line| {
+            let mut values = line
+                .split(|c: char| c == ',' || c == ';' || c.is_whitespace())
+                .filter(|v| !v.is_empty())
+                .map(|v| v.parse::<f64>());
+
+            match (values.next(), values.next()) {
+                (Some(Ok(freq)), Some(Ok(abs))) => Some(MeasuredPoint { freq, abs }),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/***********************************************************************************************************************
+ * A validated measured dataset with its points in ascending frequency order
+ */
+#[derive(Debug, Clone)]
+pub struct MeasuredSeries {
+    pub label: String,
+    pub points: Vec<MeasuredPoint>,
+}
+
+impl MeasuredSeries {
+    pub fn new(dataset: &MeasuredDataset, idx: usize) -> Result<MeasuredSeries, GenericError> {
+        let label = if dataset.label.is_empty() {
+            format!("Measured data {}", idx + 1)
+        } else {
+            dataset.label.clone()
+        };
+
+        let mut points = dataset.points.clone();
+        points.append(&mut parse_csv(&dataset.csv));
+
+        if points.is_empty() {
+            return Err(GenericError::new_measured_data_err(&label, "contains no values"));
+        }
+
+        if let Some(bad_point) = points
+            .iter()
+            .find(|p| !(p.freq.is_finite() && p.freq > 0.0 && p.abs.is_finite()))
+        {
+            return Err(GenericError::new_measured_data_err(
+                &label,
+                &format!("contains the invalid value '{:?}' at '{:?}' Hz", bad_point.abs, bad_point.freq),
+            ));
+        }
+
+        points.sort_by(|a, b| a.freq.total_cmp(&b.freq));
+
+        Ok(MeasuredSeries { label, points })
+    }
+
+    /*******************************************************************************************************************
+     * Interpolate the measured values linearly against log frequency
+     * No value is returned for frequencies outside the measured range
+     */
+    pub fn interpolate(&self, frequency: f64) -> Option<f64> {
+        let first = self.points.first()?;
+        let last = self.points.last()?;
+
+        if frequency < first.freq || frequency > last.freq {
+            return None;
+        }
+
+        let upper_idx = self.points.iter().position(|p| p.freq >= frequency)?;
+
+        if upper_idx == 0 || self.points[upper_idx].freq == frequency {
+            return Some(self.points[upper_idx].abs);
+        }
+
+        let lower = &self.points[upper_idx - 1];
+        let upper = &self.points[upper_idx];
+        let ratio = (log10(frequency) - log10(lower.freq)) / (log10(upper.freq) - log10(lower.freq));
+
+        Some(lower.abs + ratio * (upper.abs - lower.abs))
+    }
+
+    /*******************************************************************************************************************
+     * RMS difference between the measured data and a calculated series at those frequencies covered by the measurements
+     */
+    pub fn rms_deviation(&self, plot_points: &[PlotAbsPoint]) -> Option<f64> {
+        let diffs: Vec<f64> = plot_points
+            .iter()
+            .filter_map(|pp| self.interpolate(pp.freq).map(|measured| measured - pp.abs))
+            .collect();
+
+        if diffs.is_empty() {
+            None
+        } else {
+            Some((diffs.iter().map(|d| d * d).sum::<f64>() / diffs.len() as f64).sqrt())
+        }
+    }
+}
+
 /***********************************************************************************************************************
  * Display configuration
  */
+/***********************************************************************************************************************
+ * Frequency axis arguments received from the client
+ *
+ * If an explicit list of frequencies is supplied, then the start and end frequencies, the subdivisions and the band
+ * scale are ignored, and the X axis is labelled by decade rather than by band
+ */
+pub struct FrequencyArgs<'a> {
+    pub start_freq: f64,
+    pub end_freq: Option<f64>,
+    pub subdivisions: u16,
+    pub band_scale: BandScale,
+    pub band_average: bool,
+    pub decade_axis: bool,
+    pub frequency_list: &'a [f64],
+}
+
+impl FrequencyArgs<'_> {
+    pub fn default() -> FrequencyArgs<'static> {
+        FrequencyArgs {
+            start_freq: FREQ_RANGE.default,
+            end_freq: None,
+            subdivisions: DEFAULT_SUBDIVISION,
+            band_scale: BandScale::default(),
+            band_average: false,
+            decade_axis: false,
+            frequency_list: &[],
+        }
+    }
+}
+
+/***********************************************************************************************************************
+ * Chart arguments received from the client, common to every device type
+ */
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ChartArgs {
+    pub graph_start_freq: f64,
+    #[serde(default)]
+    pub graph_end_freq: Option<f64>,
+    pub smooth_curve: bool,
+    pub subdivision: u16,
+    #[serde(default)]
+    pub frequencies: Vec<f64>,
+    #[serde(default)]
+    pub band_scale: BandScale,
+    #[serde(default)]
+    pub band_average: bool,
+    #[serde(default)]
+    pub decade_axis: bool,
+    #[serde(default)]
+    pub view: Option<ViewLimits>,
+    pub show_diagram: bool,
+    #[serde(default)]
+    pub measured_data: Vec<MeasuredDataset>,
+}
+
+impl ChartArgs {
+    pub fn chart_config(&self) -> Result<ChartConfig, GenericError> {
+        ChartConfig::new(
+            FrequencyArgs {
+                start_freq: self.graph_start_freq,
+                end_freq: self.graph_end_freq,
+                subdivisions: self.subdivision,
+                band_scale: self.band_scale,
+                band_average: self.band_average,
+                decade_axis: self.decade_axis,
+                frequency_list: &self.frequencies,
+            },
+            self.view,
+            self.smooth_curve,
+            self.show_diagram,
+            &self.measured_data,
+        )
+    }
+}
+
+/***********************************************************************************************************************
+ * Display configuration
+ *
+ * The calculation engines evaluate the absorption at each of the sample frequencies.  These are the same as the chart
+ * frequencies unless band averages are required.
+ */
 #[allow(dead_code)]
+#[derive(Clone)]
 pub struct ChartConfig {
     pub graph_start_freq: f64,
+    pub graph_end_freq: f64,
     pub smooth_curve: bool,
     pub subdivisions: u16,
     pub show_diagram: bool,
+    pub band_scale: BandScale,
+    pub band_average: bool,
+    pub decade_axis: bool,
     pub frequencies: Vec<f64>,
+    pub frequency_labels: Vec<String>,
+    pub sample_frequencies: Vec<f64>,
+    pub measured_data: Vec<MeasuredSeries>,
+    pub view: ViewLimits,
 }
 
 impl ChartConfig {
     pub fn default() -> ChartConfig {
-        ChartConfig::new(FREQ_RANGE.default, false, DEFAULT_SUBDIVISION, false).unwrap()
+        ChartConfig::new(FrequencyArgs::default(), None, false, false, &[]).unwrap()
     }
 
     pub fn new(
-        start_freq_arg: f64,
+        freq_args: FrequencyArgs,
+        view_arg: Option<ViewLimits>,
         smooth_curve: bool,
-        subdivisions_arg: u16,
         show_diagram: bool,
+        measured_data_arg: &[MeasuredDataset],
     ) -> Result<ChartConfig, GenericError> {
-        if !FREQ_RANGE.contains(start_freq_arg) {
-            return Err(GenericError::new_chart_err(ErrType::Graph, start_freq_arg));
-        }
+        let (frequencies, frequency_labels) = if freq_args.frequency_list.is_empty() {
+            if !FREQ_RANGE.contains(freq_args.start_freq) {
+                return Err(GenericError::new_chart_err(ErrType::Graph, freq_args.start_freq));
+            }
 
-        if !SUBDIVISIONS.contains(&subdivisions_arg) {
-            return Err(GenericError::new_chart_err(ErrType::Subdivision, subdivisions_arg as f64));
+            let end_freq = match freq_args.end_freq {
+                Some(end_freq) if end_freq <= freq_args.start_freq || end_freq > FREQ_RANGE.max => {
+                    return Err(GenericError::new_chart_err(ErrType::GraphEnd, end_freq))
+                },
+                Some(end_freq) => end_freq,
+                // By default, the chart covers a fixed number of octaves, unless this would pass the highest frequency
+                None => (freq_args.start_freq * pow(2.0, DISPLAY_OCTAVES as f64)).min(FREQ_RANGE.max),
+            };
+
+            if !SUBDIVISIONS.contains(&freq_args.subdivisions) {
+                return Err(GenericError::new_chart_err(ErrType::Subdivision, freq_args.subdivisions as f64));
+            }
+
+            match freq_args.band_scale {
+                BandScale::Iso266 => {
+                    let band_numbers = gen_iso_band_numbers(&freq_args.start_freq, &end_freq, &freq_args.subdivisions);
+
+                    (
+                        band_numbers
+                            .iter()
+                            .map(|band_no| iso_exact_frequency(*band_no, freq_args.subdivisions))
+                            .collect::<Vec<f64>>(),
+                        band_numbers
+                            .iter()
+                            .map(|band_no| frequency_label(iso_nominal_frequency(*band_no, freq_args.subdivisions)))
+                            .collect::<Vec<String>>(),
+                    )
+                },
+                BandScale::BaseTwo => {
+                    let frequencies = gen_frequencies(&freq_args.start_freq, &end_freq, &freq_args.subdivisions);
+                    let labels = frequencies.iter().map(|f| frequency_label(*f)).collect();
+                    (frequencies, labels)
+                },
+            }
+        } else if freq_args.band_average {
+            return Err(GenericError::new_chart_err(ErrType::BandAverage, 0.0));
+        } else {
+            let frequencies = explicit_frequencies(freq_args.frequency_list)?;
+            let labels = frequencies.iter().map(|f| frequency_label(*f)).collect();
+            (frequencies, labels)
+        };
+
+        if frequencies.len() < MIN_FREQUENCIES || frequencies.len() > MAX_FREQUENCIES {
+            return Err(GenericError::new_chart_err(ErrType::FrequencyCount, frequencies.len() as f64));
         }
 
+        let sample_frequencies = if freq_args.band_average {
+            gen_band_samples(&frequencies, freq_args.band_scale.band_ratio(freq_args.subdivisions))
+        } else {
+            frequencies.clone()
+        };
+
+        let measured_data = measured_data_arg
+            .iter()
+            .enumerate()
+            .map(|(idx, dataset)| MeasuredSeries::new(dataset, idx))
+            .collect::<Result<Vec<MeasuredSeries>, GenericError>>()?;
+
+        let graph_start_freq = frequencies[0];
+        let graph_end_freq = frequencies[frequencies.len() - 1];
+
+        // Unless the chart has been zoomed or panned, the view covers all the chart frequencies and absorption values
+        let view = match view_arg {
+            Some(view) => view.validate()?,
+            None => ViewLimits {
+                freq_min: graph_start_freq,
+                freq_max: graph_end_freq,
+                abs_min: 0.0,
+                abs_max: 1.0,
+            },
+        };
+
+        // Band ticks can only be drawn when the view runs from the first to the last band
+        let zoomed = view.freq_min != graph_start_freq || view.freq_max != graph_end_freq;
+
         Ok(ChartConfig {
-            graph_start_freq: start_freq_arg,
-            subdivisions: subdivisions_arg,
+            graph_start_freq,
+            graph_end_freq,
+            subdivisions: freq_args.subdivisions,
             smooth_curve,
             show_diagram,
-            frequencies: gen_frequencies(&start_freq_arg, &subdivisions_arg),
+            band_scale: freq_args.band_scale,
+            band_average: freq_args.band_average,
+            decade_axis: freq_args.decade_axis || !freq_args.frequency_list.is_empty() || zoomed,
+            frequencies,
+            frequency_labels,
+            sample_frequencies,
+            measured_data,
+            view,
         })
     }
 }
@@ -151,6 +559,41 @@ pub struct SeriesMetadata<'a> {
     pub plot_colour: &'a str,
 }
 
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+// Measured data interpolated onto the chart frequencies, and its RMS deviation from each calculated series
+// A deviation of None means that the measured data does not overlap the chart frequencies
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[derive(Debug, Serialize)]
+pub struct SeriesDeviation<'a> {
+    pub name: &'a str,
+    pub rms_deviation: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MeasuredOverlay<'a> {
+    pub label: &'a str,
+    pub plot_points: Vec<PlotAbsPoint>,
+    pub deviations: Vec<SeriesDeviation<'a>>,
+}
+
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+// Statistical spread of a series' absorption values at each frequency
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[derive(Debug, Serialize)]
+pub struct BandPoint {
+    pub freq: f64,
+    pub nominal: f64,
+    pub mean: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SeriesBand<'a> {
+    pub name: &'a str,
+    pub band_points: Vec<BandPoint>,
+}
+
 // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
 // Font metadata
 // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
@@ -202,20 +645,183 @@ impl<'a> Axis<'a> {
     }
 }
 
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+// The frequency and absorption limits of the visible part of the chart
+//
+// Zooming is centred on a given point and scales the frequency range logarithmically.  Panning shifts the view by a
+// fraction of its current width and height.  In both cases, the frequency limits are kept within the permitted range
+// - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ViewLimits {
+    pub freq_min: f64,
+    pub freq_max: f64,
+    pub abs_min: f64,
+    pub abs_max: f64,
+}
+
+impl ViewLimits {
+    pub fn validate(self) -> Result<ViewLimits, GenericError> {
+        if !FREQ_RANGE.contains(self.freq_min) || !FREQ_RANGE.contains(self.freq_max) {
+            Err(GenericError::new_chart_err(ErrType::View, self.freq_min.min(self.freq_max)))
+        } else if self.freq_min >= self.freq_max {
+            Err(GenericError::new_chart_err(ErrType::View, self.freq_max))
+        } else if !self.abs_min.is_finite() || !self.abs_max.is_finite() || self.abs_min >= self.abs_max {
+            Err(GenericError::new_chart_err(ErrType::View, self.abs_max))
+        } else {
+            Ok(self)
+        }
+    }
+
+    pub fn zoom(&self, factor: f64, centre_freq: f64, centre_abs: f64) -> ViewLimits {
+        let log_centre = log10(centre_freq);
+
+        ViewLimits {
+            freq_min: pow(10.0, log_centre + (log10(self.freq_min) - log_centre) / factor),
+            freq_max: pow(10.0, log_centre + (log10(self.freq_max) - log_centre) / factor),
+            abs_min: centre_abs + (self.abs_min - centre_abs) / factor,
+            abs_max: centre_abs + (self.abs_max - centre_abs) / factor,
+        }
+        .clamped()
+    }
+
+    pub fn pan(&self, x_fraction: f64, y_fraction: f64) -> ViewLimits {
+        let freq_ratio = pow(self.freq_max / self.freq_min, x_fraction);
+        let abs_offset = (self.abs_max - self.abs_min) * y_fraction;
+
+        ViewLimits {
+            freq_min: self.freq_min * freq_ratio,
+            freq_max: self.freq_max * freq_ratio,
+            abs_min: self.abs_min + abs_offset,
+            abs_max: self.abs_max + abs_offset,
+        }
+        .clamped()
+    }
+
+    // Shift the frequency limits back inside the permitted range without changing the view width if possible
+    fn clamped(self) -> ViewLimits {
+        let freq_ratio = (self.freq_max / self.freq_min).min(FREQ_RANGE.max / FREQ_RANGE.min);
+
+        let (freq_min, freq_max) = if self.freq_min < FREQ_RANGE.min {
+            (FREQ_RANGE.min, FREQ_RANGE.min * freq_ratio)
+        } else if self.freq_max > FREQ_RANGE.max {
+            (FREQ_RANGE.max / freq_ratio, FREQ_RANGE.max)
+        } else {
+            (self.freq_min, self.freq_max)
+        };
+
+        ViewLimits { freq_min, freq_max, ..self }
+    }
+}
+
 // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
 // Bounding box for the chart.  This defines the bounding box within which the cross-hairs appear
+//
+// The view limits are returned to JavaScript so that canvas locations within the box can be mapped back to frequency
+// and absorption values
 // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
 #[derive(Debug, Serialize)]
 pub struct ChartBox {
     pub top_left: PlotPoint,
     pub bottom_right: PlotPoint,
+    pub view: ViewLimits,
+}
+
+impl ChartBox {
+    // Frequencies are plotted on a logarithmic axis
+    pub fn x_pos(&self, freq: f64) -> f64 {
+        self.top_left.x
+            + (self.bottom_right.x - self.top_left.x) * log10(freq / self.view.freq_min)
+                / log10(self.view.freq_max / self.view.freq_min)
+    }
+
+    pub fn y_pos(&self, abs: f64) -> f64 {
+        self.bottom_right.y
+            - (self.bottom_right.y - self.top_left.y) * (abs - self.view.abs_min)
+                / (self.view.abs_max - self.view.abs_min)
+    }
+
+    pub fn contains(&self, point: &PlotPoint) -> bool {
+        point.x >= self.top_left.x
+            && point.x <= self.bottom_right.x
+            && point.y >= self.top_left.y
+            && point.y <= self.bottom_right.y
+    }
 }
 
 // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
 // Chart Information to be returned to JavaScript
 // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+/***********************************************************************************************************************
+ * A value derived from the device arguments, such as a flow resistivity estimated from fibre properties
+ *
+ * The field identifies the argument to which the value corresponds
+ */
+#[derive(Debug, Serialize)]
+pub struct DerivedValue {
+    pub field: &'static str,
+    pub name: &'static str,
+    pub value: f64,
+    pub units: &'static str,
+}
+
 #[derive(Debug, Serialize)]
 pub struct ChartInfo<'a> {
     pub chart_box: ChartBox,
     pub series_data: Vec<SeriesData<'a>>,
+    pub measured_data: Vec<MeasuredOverlay<'a>>,
+    pub derived_values: Vec<DerivedValue>,
+    pub warnings: Vec<GenericError>,
+    pub explanation: Option<Explanation>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn iso_chart(start_freq: f64, end_freq: f64, subdivisions: u16) -> ChartConfig {
+        let freq_args = FrequencyArgs {
+            start_freq,
+            end_freq: Some(end_freq),
+            subdivisions,
+            ..FrequencyArgs::default()
+        };
+
+        ChartConfig::new(freq_args, None, false, false, &[]).unwrap()
+    }
+
+    #[test]
+    fn iso_bands_lie_within_frequency_range() {
+        for subdivisions in SUBDIVISIONS {
+            let chart = iso_chart(FREQ_RANGE.min, FREQ_RANGE.max, subdivisions);
+
+            assert!(chart.frequencies.iter().all(|freq| FREQ_RANGE.contains(*freq)));
+            assert!(chart.view.validate().is_ok(), "1/{} octave view", subdivisions);
+        }
+    }
+
+    #[test]
+    fn lowest_iso_band_is_rounded_up() {
+        // The nearest octave band centre to 10Hz is 7.94Hz
+        let chart = iso_chart(10.0, 1000.0, 1);
+
+        assert!((chart.graph_start_freq - 15.85).abs() < 0.01);
+        assert_eq!(chart.frequency_labels[0], "16");
+    }
+
+    #[test]
+    fn highest_iso_band_is_rounded_down() {
+        // The nearest half octave band centre to 20kHz is 22.4kHz
+        let chart = iso_chart(1000.0, 20000.0, 2);
+
+        assert!((chart.graph_end_freq - 15849.0).abs() < 1.0);
+        assert_eq!(chart.frequency_labels.last().unwrap(), "16000");
+    }
+
+    #[test]
+    fn iso_bands_within_range_are_kept() {
+        let chart = iso_chart(10.0, 20000.0, 3);
+
+        assert_eq!(chart.frequency_labels[0], "10");
+        assert_eq!(chart.frequency_labels.last().unwrap(), "20000");
+    }
 }
@@ -1,21 +1,41 @@
 pub const TXT_AIR_GAP: &str = "Air gap";
 pub const TXT_AIR_PRESSURE: &str = "Air pressure";
 pub const TXT_AIR_TEMP: &str = "Air temperature";
+pub const TXT_BULK_DENSITY: &str = "Bulk density";
 pub const TXT_CENTRES: &str = "Centres";
 pub const TXT_DISTANCE: &str = "Distance";
+pub const TXT_FIBRE_DIAMETER: &str = "Fibre diameter";
 pub const TXT_FLOW_RESISTIVITY: &str = "Flow resistivity";
 pub const TXT_FREQ_RANGE: &str = "Frequency range";
 pub const TXT_INCIDENT_ANGLE: &str = "Incident angle";
+pub const TXT_MIC_SPACING: &str = "Microphone spacing";
+pub const TXT_PERCENTILE: &str = "Percentile";
+pub const TXT_POROSITY: &str = "Porosity";
 pub const TXT_RADIUS: &str = "Radius";
+pub const TXT_RELATIVE_HUMIDITY: &str = "Relative humidity";
+pub const TXT_SAMPLE_DISTANCE: &str = "Distance from sample to microphone 1";
+pub const TXT_SAMPLE_COUNT: &str = "Monte Carlo sample count";
+pub const TXT_STEP_SIZE: &str = "Finite difference step size";
 pub const TXT_THICKNESS: &str = "Thickness";
+pub const TXT_TUBE_DIAMETER: &str = "Tube diameter";
 pub const TXT_WIDTH: &str = "Width";
 
 pub const UNITS_ANGLE: &str = "degrees";
 pub const UNITS_CENTRES: &str = "mm";
+pub const UNITS_DENSITY: &str = "kg/m³";
 pub const UNITS_DISTANCE: &str = "mm";
+pub const UNITS_FIBRE_DIAMETER: &str = "μm";
+pub const UNITS_FLOW_RESISTIVITY: &str = "Pa·s/m²";
 pub const UNITS_FREQ: &str = "Hz";
+pub const UNITS_INCH: &str = "in";
+pub const UNITS_IN_HG: &str = "inHg";
+pub const UNITS_PERCENT: &str = "%";
+pub const UNITS_POROSITY: &str = "";
 pub const UNITS_PRESSURE: &str = "bar";
+pub const UNITS_PSI: &str = "psi";
 pub const UNITS_RADIUS: &str = "mm";
+pub const UNITS_SAMPLES: &str = "samples";
 pub const UNITS_TEMP: &str = "°C";
+pub const UNITS_TEMP_F: &str = "°F";
 pub const UNITS_THICKNESS: &str = "mm";
 pub const UNITS_WIDTH: &str = "mm";
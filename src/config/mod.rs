@@ -1,5 +1,7 @@
 use crate::{config::ranges::NamedRange, utils::validation};
+use serde::Serialize;
 use std::fmt;
+use wasm_bindgen::JsValue;
 
 pub mod air;
 pub mod cavity;
@@ -8,56 +10,218 @@ pub mod config_set;
 pub mod constants;
 pub mod ranges;
 pub mod sound;
+pub mod units;
 
-/**********************************************************************************************************************/
-#[derive(Debug)]
+/***********************************************************************************************************************
+ * Validation errors
+ *
+ * Where an error relates to a particular argument, the field holds the name of that argument as it appears in the args
+ * struct (nested arguments are joined with a dot).  Range errors also carry the offending value and the permitted
+ * minimum and maximum so that the client can highlight the field without parsing the message text
+ *
+ * The same structure is used for warnings about argument values that can be calculated, but for which the results are
+ * of doubtful accuracy
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    OutOfRange,
+    InvalidValue,
+    Inconsistent,
+    UnknownId,
+    Malformed,
+    Unsupported,
+    ModelValidity,
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct GenericError {
+    pub field: Option<String>,
+    pub code: ErrorCode,
+    pub value: Option<f64>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
     pub msg: String,
 }
 
 impl GenericError {
-    pub fn new_from_f64(range: NamedRange<f64>, err_val: f64) -> GenericError {
+    pub fn new(code: ErrorCode, msg: String) -> GenericError {
         GenericError {
-            msg: validation::failure_msg(range, err_val),
+            field: None,
+            code,
+            value: None,
+            min: None,
+            max: None,
+            msg,
         }
     }
 
-    pub fn new_from_u16(range: NamedRange<u16>, err_val: u16) -> GenericError {
-        GenericError {
-            msg: validation::failure_msg(range, err_val),
-        }
+    pub fn for_field(mut self, field: &str) -> GenericError {
+        self.field = Some(String::from(field));
+        self
     }
-    pub fn new_from_i16(range: NamedRange<i16>, err_val: i16) -> GenericError {
-        GenericError {
-            msg: validation::failure_msg(range, err_val),
-        }
+
+    pub fn with_value(mut self, value: f64) -> GenericError {
+        self.value = Some(value);
+        self
+    }
+
+    pub fn with_limits(mut self, min: f64, max: f64) -> GenericError {
+        self.min = Some(min);
+        self.max = Some(max);
+        self
+    }
+
+    // An error in a nested argument object is reported against the path of that object
+    pub fn within(mut self, parent: &str) -> GenericError {
+        self.field = Some(match self.field {
+            Some(field) => format!("{}.{}", parent, field),
+            None => String::from(parent),
+        });
+        self
+    }
+
+    // Quote the value and limits of a length, temperature or pressure in the units of the client's request
+    pub fn in_units(mut self, metric_units: &'static str) -> GenericError {
+        self.value = self.value.map(|value| units::display_value(metric_units, value));
+        self.min = self.min.map(|min| units::display_value(metric_units, min));
+        self.max = self.max.map(|max| units::display_value(metric_units, max));
+        self
+    }
+
+    // Identify the design or dataset to which the error belongs
+    pub fn in_context(mut self, context: &str) -> GenericError {
+        self.msg = format!("{}: {}", context, self.msg);
+        self
+    }
+
+    pub fn new_from_f64(range: NamedRange<f64>, err_val: f64) -> GenericError {
+        GenericError::new_from_range(range, err_val)
+    }
+
+    pub fn new_from_u16(range: NamedRange<u16>, err_val: u16) -> GenericError {
+        GenericError::new_from_range(range, err_val)
     }
 
     pub fn new_from_u32(range: NamedRange<u32>, err_val: u32) -> GenericError {
-        GenericError {
-            msg: validation::failure_msg(range, err_val),
-        }
+        GenericError::new_from_range(range, err_val)
+    }
+
+    fn new_from_range<T: Into<f64> + Copy + fmt::Debug>(range: NamedRange<T>, err_val: T) -> GenericError {
+        let (field, units, min, max) = (range.field, range.units, range.min.into(), range.max.into());
+
+        GenericError::new(ErrorCode::OutOfRange, validation::failure_msg(range, err_val))
+            .for_field(field)
+            .with_value(err_val.into())
+            .with_limits(min, max)
+            .in_units(units)
     }
 
     pub fn new_chart_err(err_type: chart::ErrType, err_val: f64) -> GenericError {
         match err_type {
-            chart::ErrType::Graph => GenericError {
-                msg: format!(
+            chart::ErrType::Graph => GenericError::new(
+                ErrorCode::OutOfRange,
+                format!(
                     "Graph start frequency must be a value in {} between {:?} and {:?}, not '{:?}'",
                     constants::UNITS_FREQ,
                     chart::FREQ_RANGE.min,
                     chart::FREQ_RANGE.max,
                     err_val
                 ),
-            },
-            chart::ErrType::Subdivision => GenericError {
-                msg: format!(
-                    "Octave subdivisions argument must be either 1, 2, 3 or 6, not '{}'",
+            )
+            .for_field("graph_start_freq")
+            .with_value(err_val)
+            .with_limits(chart::FREQ_RANGE.min, chart::FREQ_RANGE.max),
+            chart::ErrType::GraphEnd => GenericError::new(
+                ErrorCode::OutOfRange,
+                format!(
+                    "Graph end frequency must be a value in {} greater than the start frequency and no more than {:?}, \
+                     not '{:?}'",
+                    constants::UNITS_FREQ,
+                    chart::FREQ_RANGE.max,
+                    err_val
+                ),
+            )
+            .for_field("graph_end_freq")
+            .with_value(err_val)
+            .with_limits(chart::FREQ_RANGE.min, chart::FREQ_RANGE.max),
+            chart::ErrType::Subdivision => GenericError::new(
+                ErrorCode::InvalidValue,
+                format!(
+                    "Octave subdivisions argument must be one of {:?}, not '{}'",
+                    chart::SUBDIVISIONS,
                     err_val.round()
                 ),
-            },
+            )
+            .for_field("subdivision")
+            .with_value(err_val),
+            chart::ErrType::FrequencyCount => GenericError::new(
+                ErrorCode::OutOfRange,
+                format!(
+                    "The graph must have between {} and {} frequencies, not '{}'",
+                    chart::MIN_FREQUENCIES,
+                    chart::MAX_FREQUENCIES,
+                    err_val
+                ),
+            )
+            .for_field("frequencies")
+            .with_value(err_val)
+            .with_limits(chart::MIN_FREQUENCIES as f64, chart::MAX_FREQUENCIES as f64),
+            chart::ErrType::FrequencyValue => GenericError::new(
+                ErrorCode::OutOfRange,
+                format!(
+                    "Each frequency must be a value in {} between {:?} and {:?}, not '{:?}'",
+                    constants::UNITS_FREQ,
+                    chart::FREQ_RANGE.min,
+                    chart::FREQ_RANGE.max,
+                    err_val
+                ),
+            )
+            .for_field("frequencies")
+            .with_value(err_val)
+            .with_limits(chart::FREQ_RANGE.min, chart::FREQ_RANGE.max),
+            chart::ErrType::BandAverage => GenericError::new(
+                ErrorCode::Inconsistent,
+                String::from("Band averages cannot be calculated for an explicit list of frequencies"),
+            )
+            .for_field("band_average"),
+            chart::ErrType::View => GenericError::new(
+                ErrorCode::InvalidValue,
+                format!(
+                    "Chart view limits must each have a minimum less than their maximum, and frequencies must lie \
+                     in {} between {:?} and {:?}, not '{:?}'",
+                    constants::UNITS_FREQ,
+                    chart::FREQ_RANGE.min,
+                    chart::FREQ_RANGE.max,
+                    err_val
+                ),
+            )
+            .for_field("view")
+            .with_value(err_val),
         }
     }
+
+    pub fn new_material_err(material_id: &str) -> GenericError {
+        GenericError::new(ErrorCode::UnknownId, format!("Unknown porous material '{}'", material_id))
+            .for_field("material_id")
+    }
+
+    pub fn new_missing_flow_resistivity_err() -> GenericError {
+        GenericError::new(
+            ErrorCode::Malformed,
+            String::from("One of flow_resistivity, material_id or fibre_properties is required"),
+        )
+        .for_field("flow_resistivity")
+    }
+
+    pub fn new_measured_data_err(label: &str, reason: &str) -> GenericError {
+        GenericError::new(ErrorCode::InvalidValue, format!("Measured data '{}' {}", label, reason))
+            .for_field("measured_data")
+    }
+
+    pub fn new_share_code_err(reason: &str) -> GenericError {
+        GenericError::new(ErrorCode::Malformed, format!("Invalid design link: {}", reason))
+    }
 }
 
 impl fmt::Display for GenericError {
@@ -65,3 +229,28 @@ impl fmt::Display for GenericError {
         write!(f, "{}", self.msg)
     }
 }
+
+/***********************************************************************************************************************
+ * Every entry point returns its result to JavaScript in an envelope tagged either "ok" or "errors"
+ */
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ResultEnvelope<'a, T: Serialize> {
+    Ok(&'a T),
+    Errors(&'a [GenericError]),
+}
+
+pub fn ok_to_js<T: Serialize>(value: &T) -> JsValue {
+    serde_wasm_bindgen::to_value(&ResultEnvelope::Ok(value)).unwrap()
+}
+
+pub fn errors_to_js(errors: &[GenericError]) -> JsValue {
+    serde_wasm_bindgen::to_value(&ResultEnvelope::<()>::Errors(errors)).unwrap()
+}
+
+pub fn result_to_js<T: Serialize>(result: &Result<T, Vec<GenericError>>) -> JsValue {
+    match result {
+        Ok(value) => ok_to_js(value),
+        Err(errors) => errors_to_js(errors),
+    }
+}
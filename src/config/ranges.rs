@@ -1,11 +1,14 @@
 /***********************************************************************************************************************
  * Porous Absorber Calculator - Range within which a named parameter is valid
  *
+ * The field is the name of the argument to which the range applies, as received from the client
+ *
  * (c) Chris Whealy 2020, 2024
  */
 #[derive(Debug)]
 pub struct NamedRange<T> {
     pub name: &'static str,
+    pub field: &'static str,
     pub units: &'static str,
     pub min: T,
     pub default: T,
@@ -11,6 +11,7 @@ use crate::config::{constants, ranges::NamedRange};
  */
 const ANGLE_RANGE: NamedRange<u16> = NamedRange {
     name: constants::TXT_INCIDENT_ANGLE,
+    field: "angle",
     units: constants::UNITS_ANGLE,
     min: 0,
     default: 0,
@@ -0,0 +1,329 @@
+/***********************************************************************************************************************
+ * Porous Absorber Calculator - Unit systems
+ *
+ * All calculations are performed in metric units.  A client may instead supply lengths in inches, temperatures in °F
+ * and air pressures in psi or inches of mercury by adding a "units" property to its request.  These arguments are
+ * converted to metric before they are deserialized and validated, then for the remainder of the request, values quoted
+ * in error messages, warnings and results are converted back to the client's units
+ *
+ * (c) Chris Whealy 2020, 2024
+ */
+use std::cell::Cell;
+
+use serde_derive::{Deserialize, Serialize};
+use serde_json::{Number, Value};
+
+use crate::config::constants;
+
+const MM_PER_INCH: f64 = 25.4;
+const BAR_PER_PSI: f64 = 0.0689475729;
+const BAR_PER_IN_HG: f64 = 0.0338638866;
+
+// Converted values are quoted to this number of decimal places
+const DISPLAY_PLACES: i32 = 4;
+
+// Arguments that must still be integers after conversion
+const INTEGER_FIELDS: [&str; 2] = ["absorber_thickness_mm", "air_gap_mm"];
+
+thread_local! {
+    static REQUEST_UNITS: Cell<UnitSystem> = const { Cell::new(UnitSystem::Metric) };
+}
+
+/***********************************************************************************************************************
+ * Unit systems
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnitSystem {
+    #[default]
+    Metric,
+    // Inches, °F and psi
+    Imperial,
+    // Inches, °F and inches of mercury
+    ImperialInHg,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Quantity {
+    Length,
+    Temperature,
+    Pressure,
+}
+
+impl Quantity {
+    // Arguments are identified by name, and every length argument is in mm
+    pub fn of_field(field: &str) -> Option<Quantity> {
+        match field.rsplit('.').next().unwrap_or(field) {
+            "air_temp" => Some(Quantity::Temperature),
+            "air_pressure" => Some(Quantity::Pressure),
+            name if name.ends_with("_mm") => Some(Quantity::Length),
+            _ => None,
+        }
+    }
+
+    // Ranges are identified by their metric units
+    pub fn of_units(units: &str) -> Option<Quantity> {
+        match units {
+            constants::UNITS_THICKNESS => Some(Quantity::Length),
+            constants::UNITS_TEMP => Some(Quantity::Temperature),
+            constants::UNITS_PRESSURE => Some(Quantity::Pressure),
+            _ => None,
+        }
+    }
+}
+
+impl UnitSystem {
+    // The unit system requested by the client, if any
+    pub fn of_request(args: &Value) -> Result<UnitSystem, serde_json::Error> {
+        match args.get("units") {
+            Some(units) => serde_json::from_value(units.clone()),
+            None => Ok(UnitSystem::Metric),
+        }
+    }
+
+    pub fn units(self, quantity: Quantity) -> &'static str {
+        match (self, quantity) {
+            (UnitSystem::Metric, Quantity::Length) => constants::UNITS_THICKNESS,
+            (UnitSystem::Metric, Quantity::Temperature) => constants::UNITS_TEMP,
+            (UnitSystem::Metric, Quantity::Pressure) => constants::UNITS_PRESSURE,
+            (_, Quantity::Length) => constants::UNITS_INCH,
+            (_, Quantity::Temperature) => constants::UNITS_TEMP_F,
+            (UnitSystem::Imperial, Quantity::Pressure) => constants::UNITS_PSI,
+            (UnitSystem::ImperialInHg, Quantity::Pressure) => constants::UNITS_IN_HG,
+        }
+    }
+
+    pub fn to_metric(self, quantity: Quantity, value: f64) -> f64 {
+        match quantity {
+            Quantity::Temperature if self != UnitSystem::Metric => (value - 32.0) * 5.0 / 9.0,
+            _ => value * self.scale(quantity),
+        }
+    }
+
+    pub fn metric_to_units(self, quantity: Quantity, value: f64) -> f64 {
+        match quantity {
+            Quantity::Temperature if self != UnitSystem::Metric => value * 9.0 / 5.0 + 32.0,
+            _ => value / self.scale(quantity),
+        }
+    }
+
+    // A difference between two values, such as a tolerance, is converted without any temperature offset
+    pub fn interval_to_metric(self, quantity: Quantity, interval: f64) -> f64 {
+        interval * self.scale(quantity)
+    }
+
+    // Size of one of these units in metric units
+    fn scale(self, quantity: Quantity) -> f64 {
+        match (self, quantity) {
+            (UnitSystem::Metric, _) => 1.0,
+            (_, Quantity::Length) => MM_PER_INCH,
+            (_, Quantity::Temperature) => 5.0 / 9.0,
+            (UnitSystem::Imperial, Quantity::Pressure) => BAR_PER_PSI,
+            (UnitSystem::ImperialInHg, Quantity::Pressure) => BAR_PER_IN_HG,
+        }
+    }
+}
+
+/***********************************************************************************************************************
+ * Convert every length, temperature and pressure argument to metric, including those in nested objects
+ */
+pub fn args_to_metric(args: &mut Value, units: UnitSystem) {
+    if units == UnitSystem::Metric {
+        return;
+    }
+
+    match args {
+        Value::Object(fields) => {
+            for (name, value) in fields.iter_mut() {
+                match (Quantity::of_field(name), value.as_f64()) {
+                    (Some(quantity), Some(n)) => *value = metric_value(name, units.to_metric(quantity, n)),
+                    _ => args_to_metric(value, units),
+                }
+            }
+        },
+        Value::Array(values) => values.iter_mut().for_each(|value| args_to_metric(value, units)),
+        _ => (),
+    }
+}
+
+/***********************************************************************************************************************
+ * The unit system of the request currently being handled
+ */
+pub fn request_units() -> UnitSystem {
+    REQUEST_UNITS.with(|units| units.get())
+}
+
+pub fn with_request_units<R>(units: UnitSystem, request_fn: impl FnOnce() -> R) -> R {
+    REQUEST_UNITS.with(|current| current.set(units));
+    let result = request_fn();
+    REQUEST_UNITS.with(|current| current.set(UnitSystem::Metric));
+
+    result
+}
+
+// A metric value quoted to the client, together with the units in which it is quoted
+pub fn display(metric_units: &'static str, value: f64) -> (f64, &'static str) {
+    let units = request_units();
+
+    match Quantity::of_units(metric_units) {
+        Some(quantity) if units != UnitSystem::Metric => {
+            (round_for_display(units.metric_to_units(quantity, value)), units.units(quantity))
+        },
+        _ => (value, metric_units),
+    }
+}
+
+pub fn display_value(metric_units: &'static str, value: f64) -> f64 {
+    display(metric_units, value).0
+}
+
+// Units in which the value of a numeric device argument is quoted to the client
+pub fn field_units(field: &str) -> &'static str {
+    match Quantity::of_field(field) {
+        Some(quantity) => request_units().units(quantity),
+        None => match field {
+            "flow_resistivity" => constants::UNITS_FLOW_RESISTIVITY,
+            "angle" => constants::UNITS_ANGLE,
+            "relative_humidity" => constants::UNITS_PERCENT,
+            // Porosities are dimensionless
+            _ => constants::UNITS_POROSITY,
+        },
+    }
+}
+
+// A metric value quoted in a message, such as "'5.0' mm"
+pub fn quote(metric_units: &'static str, value: f64) -> String {
+    let (value, units) = display(metric_units, value);
+    format!("'{:?}' {}", value, units)
+}
+
+//**********************************************************************************************************************
+// Private API
+//**********************************************************************************************************************
+fn metric_value(name: &str, value: f64) -> Value {
+    if INTEGER_FIELDS.contains(&name) {
+        Value::from(value.round() as i64)
+    } else {
+        Number::from_f64(value).map(Value::Number).unwrap_or(Value::Null)
+    }
+}
+
+fn round_for_display(value: f64) -> f64 {
+    let factor = 10f64.powi(DISPLAY_PLACES);
+    (value * factor).round() / factor
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    const UNIT_SYSTEMS: [UnitSystem; 3] = [UnitSystem::Metric, UnitSystem::Imperial, UnitSystem::ImperialInHg];
+    const QUANTITIES: [Quantity; 3] = [Quantity::Length, Quantity::Temperature, Quantity::Pressure];
+
+    #[test]
+    fn converts_to_metric_and_back() {
+        for units in UNIT_SYSTEMS {
+            for quantity in QUANTITIES {
+                for value in [-40.0, 0.0, 1.0, 29.92, 100.0] {
+                    let round_trip = units.metric_to_units(quantity, units.to_metric(quantity, value));
+                    assert!((round_trip - value).abs() < 1.0e-9, "{:?} {:?} {}", units, quantity, value);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn converts_known_values() {
+        assert_eq!(UnitSystem::Imperial.to_metric(Quantity::Length, 1.0), 25.4);
+        assert_eq!(UnitSystem::Imperial.to_metric(Quantity::Temperature, 212.0), 100.0);
+        assert_eq!(UnitSystem::ImperialInHg.to_metric(Quantity::Temperature, -40.0), -40.0);
+        assert!((UnitSystem::Imperial.to_metric(Quantity::Pressure, 14.6959) - 1.01325).abs() < 1.0e-5);
+        assert!((UnitSystem::ImperialInHg.to_metric(Quantity::Pressure, 29.9213) - 1.01325).abs() < 1.0e-5);
+    }
+
+    #[test]
+    fn converts_temperature_intervals_without_offset() {
+        assert_eq!(UnitSystem::Imperial.interval_to_metric(Quantity::Temperature, 9.0), 5.0);
+        assert_eq!(UnitSystem::Metric.interval_to_metric(Quantity::Temperature, 9.0), 9.0);
+    }
+
+    #[test]
+    fn identifies_quantity_of_field() {
+        assert_eq!(Quantity::of_field("air_gap_mm"), Some(Quantity::Length));
+        assert_eq!(Quantity::of_field("designs.0.device.air_temp"), Some(Quantity::Temperature));
+        assert_eq!(Quantity::of_field("air_pressure"), Some(Quantity::Pressure));
+        assert_eq!(Quantity::of_field("flow_resistivity"), None);
+        assert_eq!(Quantity::of_units(constants::UNITS_TEMP), Some(Quantity::Temperature));
+    }
+
+    #[test]
+    fn reads_units_of_request() {
+        assert_eq!(UnitSystem::of_request(&json!({})).unwrap(), UnitSystem::Metric);
+        assert_eq!(
+            UnitSystem::of_request(&json!({ "units": "imperial_in_hg" })).unwrap(),
+            UnitSystem::ImperialInHg
+        );
+        assert!(UnitSystem::of_request(&json!({ "units": "furlongs" })).is_err());
+    }
+
+    #[test]
+    fn converts_nested_args_to_metric() {
+        let mut args = json!({
+            "air_temp": 68,
+            "air_pressure": 14.5,
+            "panel_thickness_mm": 0.5,
+            "absorber_thickness_mm": 1.2,
+            "flow_resistivity": 16500,
+            "designs": [{ "device": { "air_gap_mm": 4 } }],
+        });
+
+        args_to_metric(&mut args, UnitSystem::Imperial);
+
+        assert_eq!(args["air_temp"], json!(20.0));
+        assert_eq!(args["panel_thickness_mm"], json!(12.7));
+        assert_eq!(args["absorber_thickness_mm"], json!(30));
+        assert_eq!(args["flow_resistivity"], json!(16500));
+        assert_eq!(args["designs"][0]["device"]["air_gap_mm"], json!(102));
+        assert!((args["air_pressure"].as_f64().unwrap() - 0.99974).abs() < 1.0e-5);
+    }
+
+    #[test]
+    fn converts_temperature_without_rounding() {
+        let mut args = json!({ "air_temp": 70 });
+
+        args_to_metric(&mut args, UnitSystem::Imperial);
+
+        let air_temp = args["air_temp"].as_f64().unwrap();
+        assert!((air_temp - 21.1111).abs() < 1.0e-4);
+        assert_eq!(
+            with_request_units(UnitSystem::Imperial, || display(constants::UNITS_TEMP, air_temp)),
+            (70.0, constants::UNITS_TEMP_F)
+        );
+    }
+
+    #[test]
+    fn leaves_metric_args_unchanged() {
+        let args = json!({ "air_temp": 20, "air_gap_mm": 100, "panel_thickness_mm": 12.5 });
+        let mut converted = args.clone();
+
+        args_to_metric(&mut converted, UnitSystem::Metric);
+
+        assert_eq!(converted, args);
+    }
+
+    #[test]
+    fn quotes_values_in_request_units() {
+        assert_eq!(quote(constants::UNITS_THICKNESS, 25.4), "'25.4' mm");
+        assert_eq!(
+            with_request_units(UnitSystem::Imperial, || quote(constants::UNITS_THICKNESS, 25.4)),
+            "'1.0' in"
+        );
+        assert_eq!(
+            with_request_units(UnitSystem::Imperial, || display(constants::UNITS_TEMP, 20.0)),
+            (68.0, constants::UNITS_TEMP_F)
+        );
+        assert_eq!(request_units(), UnitSystem::Metric);
+    }
+}
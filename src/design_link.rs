@@ -0,0 +1,101 @@
+/***********************************************************************************************************************
+ * Porous Absorber Calculator - Design links
+ *
+ * The arguments of a device are encoded as a short string that can be placed in the query string of a URL, allowing a
+ * design to be shared simply by sending a link.  The string has the form "v<version>.<payload>", where the payload is
+ * the unpadded base64url encoding of the device arguments as JSON.  Arguments that are null or empty are omitted from
+ * the payload because they are restored from their defaults when the link is decoded.
+ *
+ * (c) Chris Whealy 2020, 2024
+ */
+use serde_json::Value;
+use wasm_bindgen::JsValue;
+
+use crate::{
+    config::{errors_to_js, ok_to_js, result_to_js, GenericError},
+    devices::DeviceArgs,
+    trace::{trace_flags::trace_flag_for, *},
+    utils::base64url,
+};
+
+pub const MOD_NAME: &str = "design_link";
+
+pub const LINK_VERSION: u16 = 1;
+
+/***********************************************************************************************************************
+ * Encode the arguments of any device type
+ */
+pub fn encode(device: &DeviceArgs) -> String {
+    let mut args = serde_json::to_value(device).unwrap();
+
+    if let Some(fields) = args.as_object_mut() {
+        *fields = std::mem::take(fields)
+            .into_iter()
+            .filter(|(_, value)| match value {
+                Value::Null => false,
+                Value::Array(values) => !values.is_empty(),
+                _ => true,
+            })
+            .collect();
+    }
+
+    format!(
+        "v{}.{}",
+        LINK_VERSION,
+        base64url::encode(serde_json::to_string(&args).unwrap().as_bytes())
+    )
+}
+
+/***********************************************************************************************************************
+ * Decode a design link, then check that the device arguments are valid
+ */
+pub fn decode(link: &str) -> Result<DeviceArgs, Vec<GenericError>> {
+    let (version, payload) = link
+        .trim()
+        .split_once('.')
+        .ok_or_else(|| vec![GenericError::new_share_code_err("no version number")])?;
+
+    if version != format!("v{}", LINK_VERSION) {
+        return Err(vec![GenericError::new_share_code_err(&format!(
+            "version '{}' is not supported",
+            version
+        ))]);
+    }
+
+    let json = base64url::decode(payload)
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .ok_or_else(|| vec![GenericError::new_share_code_err("the link is incomplete or corrupt")])?;
+
+    let device = serde_json::from_str::<DeviceArgs>(&json)
+        .map_err(|err| vec![GenericError::new_share_code_err(&err.to_string())])?;
+
+    device.config_set().map(|_| device)
+}
+
+/***********************************************************************************************************************
+ * Handle incoming requests from JavaScript
+ *
+ * Decoding returns device arguments that can be used to populate a tab
+ */
+pub fn prepare_encode(device: DeviceArgs) -> JsValue {
+    let trace_boundary = make_boundary_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, "prepare_encode");
+    trace_boundary(TraceAction::Enter);
+
+    let result = match device.config_set() {
+        Ok(_) => ok_to_js(&encode(&device)),
+        Err(errors) => errors_to_js(&errors),
+    };
+
+    trace_boundary(TraceAction::Exit);
+    result
+}
+
+pub fn prepare_decode(link: String) -> JsValue {
+    let trace_boundary = make_boundary_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, "prepare_decode");
+    trace_boundary(TraceAction::Enter);
+
+    let result = result_to_js(&decode(&link));
+
+    trace_boundary(TraceAction::Exit);
+    result
+}
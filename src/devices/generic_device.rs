@@ -5,9 +5,12 @@
  */
 use serde_derive::Serialize;
 
-use crate::chart::constants;
+use crate::chart::{constants, render};
 use crate::{
-    config::{cavity::CavityConfig, chart::SeriesData},
+    config::{
+        cavity::CavityConfig,
+        chart::{ChartConfig, PlotAbsPoint, SeriesData, SAMPLES_PER_BAND},
+    },
     devices::{
         microperforated_panel::config::MicroperforatedPanelConfig, perforated_panel::config::PerforatedPanelConfig,
         porous_absorber::config::PorousLayerConfig, slotted_panel::config::SlottedPanelConfig,
@@ -16,7 +19,7 @@ use crate::{
 /***********************************************************************************************************************
  * Absorption data
  */
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Copy, Serialize)]
 pub enum DeviceType {
     RigidBackedPorousAbsorber,
     PerforatedPanelAbsorber,
@@ -102,4 +105,27 @@ impl<'a> GenericDeviceInfo<'a> {
             cavity,
         }
     }
+
+    /*******************************************************************************************************************
+     * When band averages are required, each series holds SAMPLES_PER_BAND values for each band that are replaced by
+     * their mean, plotted at the band centre frequency
+     */
+    pub fn band_averaged(mut self, chart_cfg: &ChartConfig) -> Self {
+        if chart_cfg.band_average {
+            for series in self.abs_series.iter_mut() {
+                series.plot_points = series
+                    .plot_points
+                    .chunks(SAMPLES_PER_BAND)
+                    .zip(chart_cfg.frequencies.iter())
+                    .map(|(band_samples, centre_freq)| PlotAbsPoint {
+                        at: render::constants::ORIGIN,
+                        freq: *centre_freq,
+                        abs: band_samples.iter().map(|pp| pp.abs).sum::<f64>() / band_samples.len() as f64,
+                    })
+                    .collect();
+            }
+        }
+
+        self
+    }
 }
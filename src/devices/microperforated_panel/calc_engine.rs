@@ -12,12 +12,7 @@ use crate::devices::{
 };
 use crate::{
     chart::render,
-    config::{
-        air::{AirConfig, AIR_VISCOSITY},
-        cavity::CavityConfig,
-        chart::PlotAbsPoint,
-        config_set::ConfigSet,
-    },
+    config::{air::AirConfig, cavity::CavityConfig, chart::PlotAbsPoint, config_set::ConfigSet},
     trace::*,
     utils::maths_functions::*,
 };
@@ -29,7 +24,10 @@ pub const MOD_NAME: &str = "devices::microperforated_panel::calc_engine";
  * Microperforated Panel Calculation
  */
 pub fn calculate_plot_points(config_set: &'_ ConfigSet) -> GenericDeviceInfo<'_> {
-    let trace_boundary = make_boundary_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, "calculate_plot_points");
+    const FN_NAME: &str = "calculate_plot_points";
+    let trace_boundary = make_boundary_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, FN_NAME);
+    let trace = make_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, FN_NAME);
+
     trace_boundary(TraceAction::Enter);
 
     let air = &config_set.air_config;
@@ -44,8 +42,9 @@ pub fn calculate_plot_points(config_set: &'_ ConfigSet) -> GenericDeviceInfo<'_>
         .unwrap();
 
     let cos_angle = cos(sound.angle as f64 * TAU / 360.0);
+    explain::record(&trace, "cos(angle of incidence)", cos_angle);
 
-    let abs_info = config_set.chart_config.frequencies.iter().fold(
+    let abs_info = config_set.chart_config.sample_frequencies.iter().fold(
         GenericDeviceInfo::new(
             DeviceType::MicroperforatedPanelAbsorber,
             None,
@@ -67,7 +66,7 @@ pub fn calculate_plot_points(config_set: &'_ ConfigSet) -> GenericDeviceInfo<'_>
     );
 
     trace_boundary(TraceAction::Exit);
-    abs_info
+    abs_info.band_averaged(&config_set.chart_config)
 }
 
 /***********************************************************************************************************************
@@ -85,6 +84,7 @@ fn calculate_plot_point(
     let trace = make_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, FN_NAME);
 
     trace_boundary(TraceAction::Enter);
+    explain::start_point(frequency);
 
     // Frequently used intermediate values
     let i: Complex<f64> = Complex::new(0.0, 1.0);
@@ -94,53 +94,53 @@ fn calculate_plot_point(
     // Wave number in air and angular frequency
     let k_air = wave_no_in_air(air_cfg, frequency);
     let omega = f_ang(frequency);
-    trace(format!("Wave number       = {}", k_air));
-    trace(format!("Angular frequency = {}", omega));
+    explain::record(&trace, "Wave number", k_air);
+    explain::record(&trace, "Angular frequency", omega);
 
     // Intermediate values for equation 6.36
     // k' from eq 6.37
     let k_prime = panel_cfg.hole_radius * sqrt(air_cfg.density_over_viscosity * omega);
-    trace(format!("k_prime = {}", k_prime));
+    explain::record(&trace, "k_prime", k_prime);
 
     // i * omega * rho * t
     let inter1 = i * omega * air_cfg.density * panel_cfg.thickness;
-    trace(format!("i * omega * rho * t = {}", inter1));
+    explain::record(&trace, "i * omega * rho * t", inter1);
 
     // k' * root of -i
     let inter2 = k_prime * sqrt_minus_i;
-    trace(format!("k_prime * sqrt(-i) = {}", inter2));
+    explain::record(&trace, "k_prime * sqrt(-i)", inter2);
 
     // Bessel function values of the first kind, zero and first orders
     let bessel_k1_0 = zbessel(0, inter2);
     let bessel_k1_1 = zbessel(1, inter2);
 
-    trace(format!("bessel_k1_0 = {}", bessel_k1_0));
-    trace(format!("bessel_k1_1 = {}", bessel_k1_1));
+    explain::record(&trace, "bessel_k1_0", bessel_k1_0);
+    explain::record(&trace, "bessel_k1_1", bessel_k1_1);
 
     // Eq 6.36
     let microperf_z1 = inter1 / (1.0 - ((2.0 * bessel_k1_1) / (inter2 * bessel_k1_0)));
-    trace(format!("Impedance at microperforated layer = {}", microperf_z1));
+    explain::record(&trace, "Impedance at microperforated layer", microperf_z1);
 
     // Intermediate values for equation 6.39
     let kd = k_air * cavity_cfg.air_gap;
-    trace(format!("kd = {}", kd));
+    explain::record(&trace, "kd", kd);
 
     let air_z2 = minus_i * air_cfg.impedance * cos(kd) / sin(kd);
-    trace(format!("Impedance at top of air layer = {}", air_z2));
+    explain::record(&trace, "Impedance at top of air layer", air_z2);
 
-    let inter3 = sqrt(2.0 * omega * air_cfg.density * AIR_VISCOSITY) / (2.0 * panel_cfg.porosity);
-    trace(format!("sqrt(2 * omega * rho * eta) / 2 * porosity = {}", inter3));
+    let inter3 = sqrt(2.0 * omega * air_cfg.density * air_cfg.dynamic_viscosity) / (2.0 * panel_cfg.porosity);
+    explain::record(&trace, "sqrt(2 * omega * rho * eta) / 2 * porosity", inter3);
 
     let inter4 = (1.7 * i * omega * air_cfg.density * panel_cfg.hole_radius) / panel_cfg.porosity;
-    trace(format!("(1.7i * omega * rho * radius) / porosity = {}", inter4));
+    explain::record(&trace, "(1.7i * omega * rho * radius) / porosity", inter4);
 
     let overall_z = ((microperf_z1 / panel_cfg.porosity) + air_z2 + inter3 + inter4) * cos_angle;
-    trace(format!("Overall impedance = {}", overall_z));
+    explain::record(&trace, "Overall impedance", overall_z);
 
     let refl = difference_over_sum(overall_z, air_cfg.impedance);
     let abs = reflectivity_as_alpha(refl);
-    trace(format!("Reflectivity = {}", refl));
-    trace(format!("Absorption coefficient = {}", abs));
+    explain::record(&trace, "Reflectivity", refl);
+    explain::record(&trace, "Absorption coefficient", abs);
 
     trace_boundary(TraceAction::Exit);
     abs
@@ -6,8 +6,9 @@
 use serde_derive::{Deserialize, Serialize};
 use super::GenericError;
 use crate::{
-    config::{constants, ranges::NamedRange},
-    utils::maths_functions::TAU,
+    config::{air::AirConfig, chart::ChartConfig, constants, ranges::NamedRange, units, ErrorCode},
+    devices::perforated_panel::config::{HolePattern, HoleShape},
+    utils::{maths_functions::TAU, validation},
 };
 
 /***********************************************************************************************************************
@@ -15,6 +16,7 @@ use crate::{
  */
 const THICKNESS_RANGE: NamedRange<f64> = NamedRange {
     name: constants::TXT_THICKNESS,
+    field: "panel_thickness_mm",
     units: constants::UNITS_THICKNESS,
     min: 0.5,
     default: 1.0,
@@ -23,6 +25,7 @@ const THICKNESS_RANGE: NamedRange<f64> = NamedRange {
 
 const CENTRES_RANGE: NamedRange<f64> = NamedRange {
     name: constants::TXT_CENTRES,
+    field: "repeat_distance_mm",
     units: constants::UNITS_CENTRES,
     min: 0.5,
     default: 5.0,
@@ -31,14 +34,17 @@ const CENTRES_RANGE: NamedRange<f64> = NamedRange {
 
 const RADIUS_RANGE: NamedRange<f64> = NamedRange {
     name: constants::TXT_RADIUS,
+    field: "hole_radius_mm",
     units: constants::UNITS_RADIUS,
     min: 0.05,
     default: 0.25,
     max: 0.5,
 };
 
-const DEFAULT_POROSITY: f64 =
-    ((TAU / 2.0) * RADIUS_RANGE.default * RADIUS_RANGE.default) / (CENTRES_RANGE.default * CENTRES_RANGE.default);
+// Maa's model assumes that the viscous boundary layer fills a significant part of each hole.  Above this value of the
+// perforate constant k' = r * sqrt(omega * rho / eta), the holes are too large for this to be true and the panel
+// behaves as an ordinary perforated panel
+const MAX_PERFORATE_CONSTANT: f64 = 10.0;
 
 /***********************************************************************************************************************
  * Perforated panel configuration
@@ -51,6 +57,7 @@ pub struct MicroperforatedPanelConfig {
     pub hole_centres_mm: f64,
     pub hole_radius: f64,
     pub hole_radius_mm: f64,
+    pub hole_pattern: HolePattern,
     pub porosity: f64,
 }
 
@@ -60,7 +67,8 @@ impl MicroperforatedPanelConfig {
             THICKNESS_RANGE.default,
             CENTRES_RANGE.default,
             RADIUS_RANGE.default,
-            DEFAULT_POROSITY,
+            HolePattern::default(),
+            None,
         )
             .unwrap()
     }
@@ -69,7 +77,8 @@ impl MicroperforatedPanelConfig {
         thickness_arg: f64,
         centres_arg: f64,
         radius_arg: f64,
-        porosity_arg: f64,
+        hole_pattern_arg: HolePattern,
+        porosity_arg: Option<f64>,
     ) -> Result<MicroperforatedPanelConfig, GenericError> {
         if !THICKNESS_RANGE.contains(thickness_arg) {
             return Err(GenericError::new_from_f64(THICKNESS_RANGE, thickness_arg));
@@ -83,6 +92,31 @@ impl MicroperforatedPanelConfig {
             return Err(GenericError::new_from_f64(RADIUS_RANGE, radius_arg));
         }
 
+        let hole_pattern = hole_pattern_arg.validate()?;
+        let nearest_centres = hole_pattern.nearest_centres(centres_arg);
+
+        // Adjacent holes must have panel material between them
+        if 2.0 * radius_arg >= nearest_centres {
+            return Err(GenericError::new(
+                ErrorCode::Inconsistent,
+                format!(
+                    "Hole radius {} must be less than half the distance of {} between adjacent holes",
+                    units::quote(RADIUS_RANGE.units, radius_arg),
+                    units::quote(CENTRES_RANGE.units, nearest_centres)
+                ),
+            )
+            .for_field(RADIUS_RANGE.field)
+            .with_value(radius_arg)
+            .with_limits(RADIUS_RANGE.min, nearest_centres / 2.0)
+            .in_units(RADIUS_RANGE.units));
+        }
+
+        let porosity = validation::porosity(
+            "porosity",
+            porosity_arg,
+            hole_pattern.porosity(HoleShape::Circular.area(radius_arg), centres_arg),
+        )?;
+
         Ok(MicroperforatedPanelConfig {
             thickness_mm: thickness_arg,
             thickness: thickness_arg / 1000.0,
@@ -90,7 +124,54 @@ impl MicroperforatedPanelConfig {
             hole_centres: centres_arg / 1000.0,
             hole_radius_mm: radius_arg,
             hole_radius: radius_arg / 1000.0,
-            porosity: porosity_arg,
+            hole_pattern,
+            porosity,
         })
     }
+
+    /*******************************************************************************************************************
+     * Combinations of argument values that can be calculated, but whose results should be treated with caution
+     *
+     * The perforate constant increases with frequency, so the Maa model is checked at the highest chart frequency and
+     * the warning quotes the frequency above which the model ceases to be valid
+     */
+    pub fn warnings(&self, air_cfg: &AirConfig, chart_cfg: &ChartConfig) -> Vec<GenericError> {
+        let mut warnings: Vec<GenericError> =
+            validation::porosity_warning("porosity", self.porosity, self.geometric_porosity())
+                .into_iter()
+                .collect();
+
+        if let Some(highest_freq) = chart_cfg.frequencies.last() {
+            let boundary_factor = (air_cfg.density_over_viscosity * TAU * highest_freq).sqrt();
+            let perforate_constant = self.hole_radius * boundary_factor;
+
+            if perforate_constant > MAX_PERFORATE_CONSTANT {
+                let valid_below_freq =
+                    (MAX_PERFORATE_CONSTANT / self.hole_radius).powi(2) / (air_cfg.density_over_viscosity * TAU);
+
+                warnings.push(
+                    GenericError::new(
+                        ErrorCode::ModelValidity,
+                        format!(
+                            "Hole radius {} is too large for the Maa microperforated panel model to be valid above \
+                             {:.0} Hz",
+                            units::quote(RADIUS_RANGE.units, self.hole_radius_mm),
+                            valid_below_freq
+                        ),
+                    )
+                    .for_field(RADIUS_RANGE.field)
+                    .with_value(self.hole_radius_mm)
+                    .with_limits(RADIUS_RANGE.min, 1000.0 * MAX_PERFORATE_CONSTANT / boundary_factor)
+                    .in_units(RADIUS_RANGE.units),
+                );
+            }
+        }
+
+        warnings
+    }
+    // The porosity of the hole pattern, which is used unless an explicit porosity is supplied
+    pub fn geometric_porosity(&self) -> f64 {
+        self.hole_pattern
+            .porosity(HoleShape::Circular.area(self.hole_radius_mm), self.hole_centres_mm)
+    }
 }
@@ -6,7 +6,7 @@
 pub mod calc_engine;
 pub mod config;
 
-use serde_derive::Deserialize;
+use serde_derive::{Deserialize, Serialize};
 use calc_engine::calculate_plot_points;
 pub use config::MicroperforatedPanelConfig;
 use wasm_bindgen::JsValue;
@@ -16,13 +16,18 @@ use crate::{
     config::{
         air::AirConfig,
         cavity::CavityConfig,
-        chart::ChartConfig,
+        chart::{ChartArgs, ChartConfig, DerivedValue},
         config_set::{ConfigSet, PanelConfigSet},
         sound::SoundConfig,
-        GenericError,
+        errors_to_js, ok_to_js, GenericError,
     },
     trace::{trace_flags::trace_flag_for, *},
-    devices::DeviceTypeArgs,
+    devices::{
+        derived_porosity,
+        generic_device::GenericDeviceInfo,
+        perforated_panel::{HolePattern, HoleShape},
+        DeviceTypeArgs,
+    },
 };
 
 pub const MOD_NAME: &str = "devices::microperforated_panel";
@@ -30,23 +35,163 @@ pub const MOD_NAME: &str = "devices::microperforated_panel";
 /***********************************************************************************************************************
  * Values receive from the client
  */
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct MicroperforatedPanelArgs {
     pub panel_thickness_mm: f64,
     pub repeat_distance_mm: f64,
     pub hole_radius_mm: f64,
-    pub porosity: f64,
+    #[serde(default)]
+    pub hole_pattern: HolePattern,
+    #[serde(default)]
+    pub porosity: Option<f64>,
     pub air_gap_mm: u16,
     pub angle: u16,
-    pub graph_start_freq: f64,
-    pub smooth_curve: bool,
-    pub subdivision: u16,
-    pub show_diagram: bool,
-    pub air_temp: i16,
+    #[serde(flatten)]
+    pub chart: ChartArgs,
+    pub air_temp: f64,
     pub air_pressure: f64,
+    #[serde(default)]
+    pub relative_humidity: f64,
 }
 
-impl DeviceTypeArgs for MicroperforatedPanelArgs {}
+impl DeviceTypeArgs for MicroperforatedPanelArgs {
+    fn chart_args(&self) -> &ChartArgs {
+        &self.chart
+    }
+
+    fn config_set_for_chart(
+        &self,
+        chart_config: Result<ChartConfig, GenericError>,
+    ) -> Result<ConfigSet, Vec<GenericError>> {
+        let mut errors: Vec<GenericError> = vec![];
+
+        // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+        // Construct set of configuration structs
+        let panel_config_set = PanelConfigSet {
+            panel_microperforated: Some(
+                MicroperforatedPanelConfig::new(
+                    self.panel_thickness_mm,
+                    self.repeat_distance_mm,
+                    self.hole_radius_mm,
+                    self.hole_pattern,
+                    self.porosity,
+                )
+                .unwrap_or_else(|err: GenericError| {
+                    errors.push(err);
+                    MicroperforatedPanelConfig::default()
+                }),
+            ),
+
+            panel_perforated: None,
+            panel_slotted: None,
+        };
+
+        let config_set = ConfigSet {
+            // Required configuration
+            air_config: AirConfig::new(self.air_temp, self.air_pressure, self.relative_humidity).unwrap_or_else(
+                |err: GenericError| {
+                    errors.push(err);
+                    AirConfig::default()
+                },
+            ),
+
+            cavity_config: CavityConfig::new(self.air_gap_mm).unwrap_or_else(|err: GenericError| {
+                errors.push(err);
+                CavityConfig::default()
+            }),
+
+            chart_config: chart_config.unwrap_or_else(|err: GenericError| {
+                errors.push(err);
+                ChartConfig::default()
+            }),
+
+            sound_config: Some(SoundConfig::new(self.angle).unwrap_or_else(|err: GenericError| {
+                errors.push(err);
+                SoundConfig::default()
+            })),
+
+            panel_config: Some(panel_config_set),
+            porous_config: None,
+        };
+
+        if errors.is_empty() {
+            Ok(config_set)
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn calculate(config_set: &ConfigSet) -> GenericDeviceInfo<'_> {
+        calculate_plot_points(config_set)
+    }
+
+    fn chart_title(config_set: &ConfigSet) -> String {
+        chart_title_at_incident_angle(
+            crate::chart::constants::CHART_TITLE_OVERALL_ABS,
+            config_set.sound_config.as_ref().unwrap().angle,
+        )
+    }
+
+    fn derived_values(&self, config_set: &ConfigSet) -> Vec<DerivedValue> {
+        let panel = config_set
+            .panel_config
+            .as_ref()
+            .unwrap()
+            .panel_microperforated
+            .as_ref()
+            .unwrap();
+
+        vec![derived_porosity("porosity", panel.geometric_porosity())]
+    }
+
+    fn warnings(&self, config_set: &ConfigSet) -> Vec<GenericError> {
+        config_set
+            .panel_config
+            .as_ref()
+            .unwrap()
+            .panel_microperforated
+            .as_ref()
+            .unwrap()
+            .warnings(&config_set.air_config, &config_set.chart_config)
+    }
+
+    fn numeric_fields(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("panel_thickness_mm", self.panel_thickness_mm),
+            ("repeat_distance_mm", self.repeat_distance_mm),
+            ("hole_radius_mm", self.hole_radius_mm),
+            (
+                "porosity",
+                self.porosity.unwrap_or_else(|| {
+                    self.hole_pattern
+                        .porosity(HoleShape::Circular.area(self.hole_radius_mm), self.repeat_distance_mm)
+                }),
+            ),
+            ("air_gap_mm", self.air_gap_mm as f64),
+            ("angle", self.angle as f64),
+            ("air_temp", self.air_temp),
+            ("air_pressure", self.air_pressure),
+            ("relative_humidity", self.relative_humidity),
+        ]
+    }
+
+    fn set_numeric_field(&mut self, field_name: &str, value: f64) -> bool {
+        match field_name {
+            "panel_thickness_mm" => self.panel_thickness_mm = value,
+            "repeat_distance_mm" => self.repeat_distance_mm = value,
+            "hole_radius_mm" => self.hole_radius_mm = value,
+            "porosity" => self.porosity = Some(value),
+            "air_gap_mm" => self.air_gap_mm = value.round() as u16,
+            "angle" => self.angle = value.round() as u16,
+            "air_temp" => self.air_temp = value,
+            "air_pressure" => self.air_pressure = value,
+            "relative_humidity" => self.relative_humidity = value,
+            _ => return false,
+        }
+
+        true
+    }
+}
 
 /***********************************************************************************************************************
  * Handle incoming arguments for calculating the absorption of a micro-perforated panel absorption device
@@ -55,85 +200,32 @@ pub fn prepare(arg_obj: MicroperforatedPanelArgs) -> JsValue {
     let trace_boundary = make_boundary_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, "prepare");
     trace_boundary(TraceAction::Enter);
 
-    // Empty return data structure
-    let mut error_msgs: Vec<String> = vec![];
-
-    // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
-    // Construct set of configuration structs
-    let panel_config_set = PanelConfigSet {
-        panel_microperforated: Some(
-            MicroperforatedPanelConfig::new(
-                arg_obj.panel_thickness_mm,
-                arg_obj.repeat_distance_mm,
-                arg_obj.hole_radius_mm,
-                arg_obj.porosity,
-            )
-            .unwrap_or_else(|err: GenericError| {
-                error_msgs.push(err.to_string());
-                MicroperforatedPanelConfig::default()
-            }),
-        ),
+    // If the arguments are valid, then calculate the absorption values, plot the graph and return the chart
+    // information, else return the validation errors
+    let series_data = match arg_obj.config_set() {
+        Ok(config_set) => {
+            let (absorber_info, explanation) = explain::explained(|| calculate_plot_points(&config_set));
 
-        panel_perforated: None,
-        panel_slotted: None,
-    };
+            // Plot the graph
+            let mut chart_info = crate::chart::render::generic_device(
+                absorber_info,
+                &config_set.chart_config,
+                &MicroperforatedPanelArgs::chart_title(&config_set),
+            );
 
-    let config_set = ConfigSet {
-        // Required configuration
-        air_config: AirConfig::new(arg_obj.air_temp, arg_obj.air_pressure).unwrap_or_else(|err: GenericError| {
-            error_msgs.push(err.to_string());
-            AirConfig::default()
-        }),
-
-        cavity_config: CavityConfig::new(arg_obj.air_gap_mm).unwrap_or_else(|err: GenericError| {
-            error_msgs.push(err.to_string());
-            CavityConfig::default()
-        }),
-
-        chart_config: ChartConfig::new(
-            arg_obj.graph_start_freq,
-            arg_obj.smooth_curve,
-            arg_obj.subdivision,
-            arg_obj.show_diagram,
-        )
-        .unwrap_or_else(|err: GenericError| {
-            error_msgs.push(err.to_string());
-            ChartConfig::default()
-        }),
-
-        sound_config: Some(SoundConfig::new(arg_obj.angle).unwrap_or_else(|err: GenericError| {
-            error_msgs.push(err.to_string());
-            SoundConfig::default()
-        })),
-
-        panel_config: Some(panel_config_set),
-        porous_config: None,
-    };
+            chart_info.derived_values = arg_obj.derived_values(&config_set);
+            chart_info.warnings = arg_obj.warnings(&config_set);
+            chart_info.explanation = explanation;
 
-    // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
-    // If there are no error messages, then calculate the absorption values, plot the graph and return the placeholder
-    // value "Ok", else return the array of error messages
-    let series_data = if error_msgs.is_empty() {
-        let absorber_info = calculate_plot_points(&config_set);
-
-        // Plot the graph
-        let chart_info = crate::chart::render::generic_device(
-            absorber_info,
-            &config_set.chart_config,
-            &chart_title_at_incident_angle(
-                crate::chart::constants::CHART_TITLE_OVERALL_ABS,
-                config_set.sound_config.as_ref().unwrap().angle,
-            ),
-        );
+            ok_to_js(&chart_info)
+        },
 
-        serde_wasm_bindgen::to_value(&chart_info).unwrap()
-    } else {
-        // Serialize the error message(s)
-        serde_wasm_bindgen::to_value(&error_msgs).unwrap()
+        // Serialize the errors
+        Err(errors) => errors_to_js(&errors),
     };
 
     trace_boundary(TraceAction::Exit);
 
-    // Return either the {X,Y} values of plot points or the error messages back to JavaScript
+    // Return either the {X,Y} values of plot points or the errors back to JavaScript
     series_data
 }
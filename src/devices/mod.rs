@@ -4,4 +4,126 @@ pub mod porous_absorber;
 pub mod slotted_panel;
 pub mod generic_device;
 
-pub trait DeviceTypeArgs {}
+use serde_derive::{Deserialize, Serialize};
+
+use crate::config::{
+    chart::{ChartArgs, ChartConfig, DerivedValue},
+    config_set::ConfigSet,
+    constants, GenericError,
+};
+use generic_device::GenericDeviceInfo;
+use microperforated_panel::MicroperforatedPanelArgs;
+use perforated_panel::PerforatedPanelArgs;
+use porous_absorber::PorousAbsorberArgs;
+use slotted_panel::SlottedPanelArgs;
+
+/***********************************************************************************************************************
+ * Behaviour common to the argument objects of every device type
+ *
+ * Numeric fields are exposed by name so that analyses such as Monte Carlo uncertainty can vary an argument value, then
+ * rebuild and recalculate the device without knowing its concrete type.  Integer fields are rounded when set.
+ *
+ * The chart is configured separately from the device so that several devices can be plotted on the same chart
+ */
+pub trait DeviceTypeArgs: Clone {
+    fn chart_args(&self) -> &ChartArgs;
+    fn config_set_for_chart(
+        &self,
+        chart_config: Result<ChartConfig, GenericError>,
+    ) -> Result<ConfigSet, Vec<GenericError>>;
+
+    fn config_set(&self) -> Result<ConfigSet, Vec<GenericError>> {
+        self.config_set_for_chart(self.chart_args().chart_config())
+    }
+
+    fn calculate(config_set: &ConfigSet) -> GenericDeviceInfo<'_>;
+    fn chart_title(config_set: &ConfigSet) -> String;
+
+    // Values calculated from the arguments rather than supplied by the client
+    fn derived_values(&self, _config_set: &ConfigSet) -> Vec<DerivedValue> {
+        vec![]
+    }
+
+    // Argument values that are valid individually, but whose combination makes the calculated results doubtful
+    fn warnings(&self, _config_set: &ConfigSet) -> Vec<GenericError> {
+        vec![]
+    }
+
+    fn numeric_fields(&self) -> Vec<(&'static str, f64)>;
+    fn set_numeric_field(&mut self, field_name: &str, value: f64) -> bool;
+
+    fn numeric_field(&self, field_name: &str) -> Option<f64> {
+        self.numeric_fields()
+            .iter()
+            .find(|(name, _)| *name == field_name)
+            .map(|(_, value)| *value)
+    }
+}
+
+/***********************************************************************************************************************
+ * The arguments of any device type, identified by a "device_type" property
+ *
+ * This allows a single request to contain a mixture of device types
+ */
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "device_type", rename_all = "snake_case")]
+pub enum DeviceArgs {
+    PorousAbsorber(PorousAbsorberArgs),
+    SlottedPanel(SlottedPanelArgs),
+    PerforatedPanel(PerforatedPanelArgs),
+    MicroperforatedPanel(MicroperforatedPanelArgs),
+}
+
+impl DeviceArgs {
+    pub fn config_set(&self) -> Result<ConfigSet, Vec<GenericError>> {
+        match self {
+            DeviceArgs::PorousAbsorber(args) => args.config_set(),
+            DeviceArgs::SlottedPanel(args) => args.config_set(),
+            DeviceArgs::PerforatedPanel(args) => args.config_set(),
+            DeviceArgs::MicroperforatedPanel(args) => args.config_set(),
+        }
+    }
+
+    pub fn config_set_for_chart(
+        &self,
+        chart_config: Result<ChartConfig, GenericError>,
+    ) -> Result<ConfigSet, Vec<GenericError>> {
+        match self {
+            DeviceArgs::PorousAbsorber(args) => args.config_set_for_chart(chart_config),
+            DeviceArgs::SlottedPanel(args) => args.config_set_for_chart(chart_config),
+            DeviceArgs::PerforatedPanel(args) => args.config_set_for_chart(chart_config),
+            DeviceArgs::MicroperforatedPanel(args) => args.config_set_for_chart(chart_config),
+        }
+    }
+
+    pub fn calculate<'a>(&self, config_set: &'a ConfigSet) -> GenericDeviceInfo<'a> {
+        match self {
+            DeviceArgs::PorousAbsorber(_) => PorousAbsorberArgs::calculate(config_set),
+            DeviceArgs::SlottedPanel(_) => SlottedPanelArgs::calculate(config_set),
+            DeviceArgs::PerforatedPanel(_) => PerforatedPanelArgs::calculate(config_set),
+            DeviceArgs::MicroperforatedPanel(_) => MicroperforatedPanelArgs::calculate(config_set),
+        }
+    }
+
+    pub fn warnings(&self, config_set: &ConfigSet) -> Vec<GenericError> {
+        match self {
+            DeviceArgs::PorousAbsorber(args) => args.warnings(config_set),
+            DeviceArgs::SlottedPanel(args) => args.warnings(config_set),
+            DeviceArgs::PerforatedPanel(args) => args.warnings(config_set),
+            DeviceArgs::MicroperforatedPanel(args) => args.warnings(config_set),
+        }
+    }
+}
+
+/***********************************************************************************************************************
+ * The geometric porosity of a panel is always reported back to the client, even when an explicit porosity is used in
+ * its place
+ */
+pub fn derived_porosity(field: &'static str, geometric_porosity: f64) -> DerivedValue {
+    DerivedValue {
+        field,
+        name: constants::TXT_POROSITY,
+        value: geometric_porosity,
+        units: constants::UNITS_POROSITY,
+    }
+}
@@ -3,13 +3,13 @@
  *
  * (c) Chris Whealy 2020, 2024
  */
-use libm::{pow, sqrt};
+use libm::sqrt;
 use num::complex::Complex;
 
 use crate::devices::generic_device::{DeviceType, GenericDeviceInfo};
 use crate::{
     chart::render,
-    config::{air::AIR_VISCOSITY, chart::PlotAbsPoint, config_set::ConfigSet},
+    config::{chart::PlotAbsPoint, config_set::ConfigSet},
     trace::*,
     utils::maths_functions::*,
 };
@@ -32,13 +32,12 @@ pub fn calculate_plot_points(config_set: &'_ ConfigSet) -> GenericDeviceInfo<'_>
     let porous = config_set.porous_config.as_ref().unwrap();
 
     // Calculate apparent panel thickness
-    let end_correction_delta = 0.8 * (1.0 - 1.47 * sqrt(panel.porosity) + 0.47 * sqrt(pow(panel.porosity, 3.0)));
-    let end_corrected_panel_thickness = panel.thickness + (2.0 * panel.hole_radius * end_correction_delta);
+    let end_corrected_panel_thickness = panel.thickness + panel.end_correction;
 
-    trace(format!("End correction delta          = {}", &end_correction_delta));
-    trace(format!("End corrected panel thickness = {}", &end_corrected_panel_thickness));
+    explain::record(&trace, "End correction", panel.end_correction);
+    explain::record(&trace, "End corrected panel thickness", end_corrected_panel_thickness);
 
-    let abs_info = config_set.chart_config.frequencies.iter().fold(
+    let abs_info = config_set.chart_config.sample_frequencies.iter().fold(
         GenericDeviceInfo::new(
             DeviceType::PerforatedPanelAbsorber,
             None,
@@ -76,7 +75,7 @@ pub fn calculate_plot_points(config_set: &'_ ConfigSet) -> GenericDeviceInfo<'_>
     );
 
     trace_boundary(TraceAction::Exit);
-    abs_info
+    abs_info.band_averaged(&config_set.chart_config)
 }
 
 /***********************************************************************************************************************
@@ -88,6 +87,7 @@ fn calculate_plot_point(frequency: f64, config_set: &ConfigSet, ec_panel_thickne
     let trace = make_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, FN_NAME);
 
     trace_boundary(TraceAction::Enter);
+    explain::start_point(frequency);
 
     let air_cfg = &config_set.air_config;
     let cavity_cfg = &config_set.cavity_config;
@@ -101,13 +101,13 @@ fn calculate_plot_point(frequency: f64, config_set: &ConfigSet, ec_panel_thickne
     // Wave number in air and angular frequency
     let k_air = wave_no_in_air(&air_cfg, frequency);
     let omega = f_ang(frequency);
-    trace(format!("Wave number       = {}", k_air));
-    trace(format!("Angular frequency = {}", omega));
+    explain::record(&trace, "Wave number", k_air);
+    explain::record(&trace, "Angular frequency", omega);
 
     // Characteristic absorber impedance and wave number
     let (z_abs, wave_no_abs) = absorber_props(&air_cfg, porous_cfg, &frequency);
-    trace(format!("Characteristic impedance = {}", z_abs));
-    trace(format!("Complex wave number      = {}", wave_no_abs));
+    explain::record(&trace, "Characteristic impedance", z_abs);
+    explain::record(&trace, "Complex wave number", wave_no_abs);
 
     // Intermediate terms
     let inter1 = k_air * cavity_cfg.air_gap;
@@ -115,19 +115,20 @@ fn calculate_plot_point(frequency: f64, config_set: &ConfigSet, ec_panel_thickne
     let inter2 = wave_no_abs * porous_cfg.thickness;
     let cot_inter2 = inter2.cos() / inter2.sin();
 
-    trace(format!("k air * t air      = {}", inter1));
-    trace(format!("cot(k air * t air) = {}", cot_inter1));
-    trace(format!("k cmplx_abs * t cmplx_abs      = {}", inter2));
-    trace(format!("cot(k cmplx_abs * t cmplx_abs) = {}", cot_inter2));
+    explain::record(&trace, "k air * t air", inter1);
+    explain::record(&trace, "cot(k air * t air)", cot_inter1);
+    explain::record(&trace, "k cmplx_abs * t cmplx_abs", inter2);
+    explain::record(&trace, "cot(k cmplx_abs * t cmplx_abs)", cot_inter2);
 
     // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
     // Absorber against panel
     let abs_against_panel_z1 = minus_i * air_cfg.impedance * cot_inter1;
     let abs_against_panel_z2 = ((minus_i * abs_against_panel_z1 * z_abs * cot_inter2) + (z_abs * z_abs))
         / (abs_against_panel_z1 - (i * z_abs * cot_inter2));
+    // The viscous losses within a non-circular hole depend on its hydraulic radius
     let surface_resistence = (air_cfg.density / panel_cfg.porosity)
-        * sqrt(8.0 * AIR_VISCOSITY * omega)
-        * (1.0 + ec_panel_thickness / (2.0 * panel_cfg.hole_radius));
+        * sqrt(8.0 * air_cfg.kinematic_viscosity * omega)
+        * (1.0 + ec_panel_thickness / (2.0 * panel_cfg.hydraulic_radius));
     let abs_against_panel_z3 = ((i / panel_cfg.porosity) * ec_panel_thickness * omega * air_cfg.density)
         + abs_against_panel_z2
         + surface_resistence;
@@ -135,13 +136,13 @@ fn calculate_plot_point(frequency: f64, config_set: &ConfigSet, ec_panel_thickne
     let abs_against_panel_refl = difference_over_sum(abs_against_panel_z3, air_cfg.impedance);
     let abs_against_panel_alpha = reflectivity_as_alpha(abs_against_panel_refl);
 
-    trace(format!("Absorber against panel z1 = {}", abs_against_panel_z1));
-    trace(format!("Absorber against panel z2 = {}", abs_against_panel_z2));
-    trace(format!("Surface resistance        = {}", surface_resistence));
-    trace(format!("Overall impedence         = {}", abs_against_panel_z3));
+    explain::record(&trace, "Absorber against panel z1", abs_against_panel_z1);
+    explain::record(&trace, "Absorber against panel z2", abs_against_panel_z2);
+    explain::record(&trace, "Surface resistance", surface_resistence);
+    explain::record(&trace, "Overall impedence", abs_against_panel_z3);
 
-    trace(format!("Absorber against panel reflection = {}", abs_against_panel_refl));
-    trace(format!("Absorber against panel absorption = {}", abs_against_panel_alpha));
+    explain::record(&trace, "Absorber against panel reflection", abs_against_panel_refl);
+    explain::record(&trace, "Absorber against panel absorption", abs_against_panel_alpha);
 
     // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
     // Absorber against backing
@@ -151,20 +152,20 @@ fn calculate_plot_point(frequency: f64, config_set: &ConfigSet, ec_panel_thickne
         / (abs_against_backing_z1 - (i * air_cfg.impedance * cot_inter1));
 
     let abs_against_backing_z3 = ((air_cfg.density / panel_cfg.porosity)
-        * sqrt(8.0 * AIR_VISCOSITY * omega)
-        * ((panel_cfg.thickness / 2.0 * panel_cfg.hole_radius) + 1.0))
+        * sqrt(8.0 * air_cfg.kinematic_viscosity * omega)
+        * ((panel_cfg.thickness / 2.0 * panel_cfg.hydraulic_radius) + 1.0))
         + ((ec_panel_thickness / panel_cfg.porosity) * i * omega * air_cfg.density)
         + abs_against_backing_z2;
 
     let abs_against_backing_refl = difference_over_sum(abs_against_backing_z3, air_cfg.impedance);
     let abs_against_backing_alpha = reflectivity_as_alpha(abs_against_backing_refl);
 
-    trace(format!("Absorber against backing z1 = {}", abs_against_backing_z1));
-    trace(format!("Absorber against backing z2 = {}", abs_against_backing_z2));
-    trace(format!("Absorber against backing z3 = {}", abs_against_backing_z3));
+    explain::record(&trace, "Absorber against backing z1", abs_against_backing_z1);
+    explain::record(&trace, "Absorber against backing z2", abs_against_backing_z2);
+    explain::record(&trace, "Absorber against backing z3", abs_against_backing_z3);
 
-    trace(format!("Absorber against backing reflection = {}", abs_against_backing_refl));
-    trace(format!("Absorber against backing absorption = {}", abs_against_backing_alpha));
+    explain::record(&trace, "Absorber against backing reflection", abs_against_backing_refl);
+    explain::record(&trace, "Absorber against backing absorption", abs_against_backing_alpha);
 
     // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
     // Absorber with no air gap
@@ -177,12 +178,12 @@ fn calculate_plot_point(frequency: f64, config_set: &ConfigSet, ec_panel_thickne
     let no_air_gap_refl = difference_over_sum(no_air_gap_z2, air_cfg.impedance);
     let no_air_gap_alpha = reflectivity_as_alpha(no_air_gap_refl);
 
-    trace(format!("cot(complex wave no * cavity depth) = {}", cot_inter3));
-    trace(format!("No air gap z1 = {}", no_air_gap_z1));
-    trace(format!("No air gap z2 = {}", no_air_gap_z2));
+    explain::record(&trace, "cot(complex wave no * cavity depth)", cot_inter3);
+    explain::record(&trace, "No air gap z1", no_air_gap_z1);
+    explain::record(&trace, "No air gap z2", no_air_gap_z2);
 
-    trace(format!("No air gap reflection = {}", no_air_gap_refl));
-    trace(format!("No air gap absorption = {}", no_air_gap_alpha));
+    explain::record(&trace, "No air gap reflection", no_air_gap_refl);
+    explain::record(&trace, "No air gap absorption", no_air_gap_alpha);
 
     trace_boundary(TraceAction::Exit);
     (no_air_gap_alpha, abs_against_panel_alpha, abs_against_backing_alpha)
@@ -6,15 +6,18 @@
 use serde_derive::{Deserialize, Serialize};
 use super::GenericError;
 use crate::{
-    config::{constants, ranges::NamedRange},
-    utils::{maths_functions::TAU},
+    config::{constants, ranges::NamedRange, units, ErrorCode},
+    utils::{maths_functions::TAU, validation},
 };
+use libm::{log, pow, sin, sqrt};
+use std::f64::consts::SQRT_2;
 
 /***********************************************************************************************************************
  * Range check values
  */
 const THICKNESS_RANGE: NamedRange<f64> = NamedRange {
     name: constants::TXT_THICKNESS,
+    field: "panel_thickness_mm",
     units: constants::UNITS_THICKNESS,
     min: 1.0,
     default: 10.0,
@@ -23,6 +26,7 @@ const THICKNESS_RANGE: NamedRange<f64> = NamedRange {
 
 const CENTRES_RANGE: NamedRange<f64> = NamedRange {
     name: constants::TXT_CENTRES,
+    field: "repeat_distance_mm",
     units: constants::UNITS_CENTRES,
     min: 2.0,
     default: 25.4,
@@ -31,14 +35,188 @@ const CENTRES_RANGE: NamedRange<f64> = NamedRange {
 
 const RADIUS_RANGE: NamedRange<f64> = NamedRange {
     name: constants::TXT_RADIUS,
+    field: "hole_radius_mm",
     units: constants::UNITS_RADIUS,
     min: 1.0,
-    default: 12.7,
+    default: 5.0,
     max: 50.0,
 };
 
-const DEFAULT_POROSITY: f64 =
-    ((TAU / 2.0) * RADIUS_RANGE.default * RADIUS_RANGE.default) / (CENTRES_RANGE.default * CENTRES_RANGE.default);
+/***********************************************************************************************************************
+ * Arrangement of the holes in the panel
+ *
+ * The hole centres are the distance between adjacent holes in the same row.  In a hexagonal (60° staggered) pattern,
+ * each hole is the same distance from its six neighbours.  In a staggered (45°) pattern, alternate rows are offset by
+ * half the hole centres and the rows are half the hole centres apart.  A custom pattern gives the open area directly
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HolePattern {
+    #[default]
+    Square,
+    Hexagonal,
+    Staggered,
+    Custom {
+        open_area_pct: f64,
+    },
+}
+
+impl HolePattern {
+    pub fn porosity(&self, hole_area: f64, centres: f64) -> f64 {
+        match self {
+            HolePattern::Square => hole_area / (centres * centres),
+            HolePattern::Hexagonal => hole_area / (centres * self.row_pitch(centres)),
+            HolePattern::Staggered => hole_area / (centres * self.row_pitch(centres)),
+            HolePattern::Custom { open_area_pct } => open_area_pct / 100.0,
+        }
+    }
+
+    // Distance between the centres of the nearest pair of holes
+    pub fn nearest_centres(&self, centres: f64) -> f64 {
+        match self {
+            HolePattern::Staggered => centres / SQRT_2,
+            _ => centres,
+        }
+    }
+
+    // Distance between successive rows of holes
+    pub fn row_pitch(&self, centres: f64) -> f64 {
+        match self {
+            HolePattern::Hexagonal => centres * sqrt(3.0) / 2.0,
+            HolePattern::Staggered => centres / 2.0,
+            _ => centres,
+        }
+    }
+
+    // Are alternate rows offset by half the hole centres?
+    pub fn is_staggered(&self) -> bool {
+        matches!(self, HolePattern::Hexagonal | HolePattern::Staggered)
+    }
+
+    pub fn validate(self) -> Result<HolePattern, GenericError> {
+        match self {
+            HolePattern::Custom { open_area_pct } if !(open_area_pct > 0.0 && open_area_pct < 100.0) => {
+                Err(GenericError::new(
+                    ErrorCode::OutOfRange,
+                    format!(
+                        "Open area must be a value in {} greater than 0 and less than 100, not '{:?}'",
+                        constants::UNITS_PERCENT,
+                        open_area_pct
+                    ),
+                )
+                .for_field("hole_pattern.custom.open_area_pct")
+                .with_value(open_area_pct)
+                .with_limits(0.0, 100.0))
+            },
+            _ => Ok(self),
+        }
+    }
+}
+
+/***********************************************************************************************************************
+ * Shape of the holes in the panel
+ *
+ * The hole radius is half the width of the hole, so a square hole has sides twice the hole radius and a slot has a
+ * width twice the hole radius.  Slots run along the rows of the hole pattern.  The viscous losses within a hole depend
+ * on its hydraulic radius, and the end corrections on its shape
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HoleShape {
+    #[default]
+    Circular,
+    Square,
+    Slot {
+        length_mm: f64,
+    },
+}
+
+impl HoleShape {
+    pub fn area(&self, radius: f64) -> f64 {
+        match self {
+            HoleShape::Circular => (TAU / 2.0) * radius * radius,
+            HoleShape::Square => 4.0 * radius * radius,
+            HoleShape::Slot { length_mm } => 2.0 * radius * length_mm,
+        }
+    }
+
+    pub fn perimeter(&self, radius: f64) -> f64 {
+        match self {
+            HoleShape::Circular => TAU * radius,
+            HoleShape::Square => 8.0 * radius,
+            HoleShape::Slot { length_mm } => 2.0 * (2.0 * radius + length_mm),
+        }
+    }
+
+    // Twice the area over the perimeter, which for a circular hole is its radius
+    pub fn hydraulic_radius(&self, radius: f64) -> f64 {
+        2.0 * self.area(radius) / self.perimeter(radius)
+    }
+
+    /*******************************************************************************************************************
+     * Total end correction of both faces of the panel
+     *
+     * A square hole is given the end correction of the circular hole having the same area.  A slot is treated as a
+     * slit, whose end correction depends on the ratio of its width to the distance between the rows of slots
+     */
+    pub fn end_correction(&self, radius: f64, porosity: f64, row_pitch: f64) -> f64 {
+        match self {
+            HoleShape::Slot { .. } => {
+                let width = 2.0 * radius;
+                let delta = -log(sin(TAU * width / row_pitch / 4.0)) * 2.0 / TAU;
+
+                2.0 * width * delta
+            },
+            _ => {
+                let equivalent_radius = sqrt(2.0 * self.area(radius) / TAU);
+                let delta = 0.8 * (1.0 - 1.47 * sqrt(porosity) + 0.47 * sqrt(pow(porosity, 3.0)));
+
+                2.0 * equivalent_radius * delta
+            },
+        }
+    }
+
+    /*******************************************************************************************************************
+     * Width of the hole above which it would touch or overlap its neighbours
+     *
+     * The sides of square holes and slots are aligned with the rows.  In a hexagonal or staggered pattern, holes in
+     * adjacent rows are therefore half the hole centres apart along the row and the row pitch apart across it, and
+     * holes two rows apart lie directly above each other
+     */
+    pub fn max_width(&self, hole_pattern: &HolePattern, centres: f64) -> f64 {
+        let row_pitch = hole_pattern.row_pitch(centres);
+
+        match self {
+            HoleShape::Circular => hole_pattern.nearest_centres(centres),
+            _ if !hole_pattern.is_staggered() => centres,
+            HoleShape::Square => centres.min((centres / 2.0).max(row_pitch)),
+            HoleShape::Slot { length_mm } if *length_mm >= centres / 2.0 => row_pitch,
+            HoleShape::Slot { .. } => 2.0 * row_pitch,
+        }
+    }
+
+    // A slot must be at least as long as it is wide, and must not reach the next slot in the row
+    pub fn validate(self, radius: f64, centres: f64) -> Result<HoleShape, GenericError> {
+        match self {
+            HoleShape::Slot { length_mm } if !(length_mm >= 2.0 * radius && length_mm < centres) => {
+                Err(GenericError::new(
+                    ErrorCode::OutOfRange,
+                    format!(
+                        "Slot length {} must be at least the slot width {} and less than the hole centres {}",
+                        units::quote(RADIUS_RANGE.units, length_mm),
+                        units::quote(RADIUS_RANGE.units, 2.0 * radius),
+                        units::quote(CENTRES_RANGE.units, centres)
+                    ),
+                )
+                .for_field("hole_shape.slot.length_mm")
+                .with_value(length_mm)
+                .with_limits(2.0 * radius, centres)
+                .in_units(RADIUS_RANGE.units))
+            },
+            _ => Ok(self),
+        }
+    }
+}
 
 /***********************************************************************************************************************
  * Perforated panel configuration
@@ -51,6 +229,10 @@ pub struct PerforatedPanelConfig {
     pub hole_centres_mm: f64,
     pub hole_radius: f64,
     pub hole_radius_mm: f64,
+    pub hole_shape: HoleShape,
+    pub hydraulic_radius: f64,
+    pub end_correction: f64,
+    pub hole_pattern: HolePattern,
     pub porosity: f64,
 }
 
@@ -60,7 +242,9 @@ impl PerforatedPanelConfig {
             THICKNESS_RANGE.default,
             CENTRES_RANGE.default,
             RADIUS_RANGE.default,
-            DEFAULT_POROSITY,
+            HoleShape::default(),
+            HolePattern::default(),
+            None,
         )
             .unwrap()
     }
@@ -69,7 +253,9 @@ impl PerforatedPanelConfig {
         thickness_arg: f64,
         centres_arg: f64,
         radius_arg: f64,
-        porosity_arg: f64,
+        hole_shape_arg: HoleShape,
+        hole_pattern_arg: HolePattern,
+        porosity_arg: Option<f64>,
     ) -> Result<PerforatedPanelConfig, GenericError> {
         if thickness_arg < THICKNESS_RANGE.min || thickness_arg > THICKNESS_RANGE.max {
             return Err(GenericError::new_from_f64(THICKNESS_RANGE, thickness_arg));
@@ -83,6 +269,34 @@ impl PerforatedPanelConfig {
             return Err(GenericError::new_from_f64(RADIUS_RANGE, radius_arg));
         }
 
+        let hole_shape = hole_shape_arg.validate(radius_arg, centres_arg)?;
+        let hole_pattern = hole_pattern_arg.validate()?;
+        let max_width = hole_shape.max_width(&hole_pattern, centres_arg);
+
+        // Adjacent holes must have panel material between them
+        if 2.0 * radius_arg >= max_width {
+            return Err(GenericError::new(
+                ErrorCode::Inconsistent,
+                format!(
+                    "Hole radius {} must be less than {} so that adjacent holes in this pattern do not touch",
+                    units::quote(RADIUS_RANGE.units, radius_arg),
+                    units::quote(RADIUS_RANGE.units, max_width / 2.0)
+                ),
+            )
+            .for_field(RADIUS_RANGE.field)
+            .with_value(radius_arg)
+            .with_limits(RADIUS_RANGE.min, max_width / 2.0)
+            .in_units(RADIUS_RANGE.units));
+        }
+
+        let porosity = validation::porosity(
+            "porosity",
+            porosity_arg,
+            hole_pattern.porosity(hole_shape.area(radius_arg), centres_arg),
+        )?;
+
+        let end_correction_mm = hole_shape.end_correction(radius_arg, porosity, hole_pattern.row_pitch(centres_arg));
+
         Ok(PerforatedPanelConfig {
             thickness_mm: thickness_arg,
             thickness: thickness_arg / 1000.0,
@@ -90,7 +304,136 @@ impl PerforatedPanelConfig {
             hole_centres: centres_arg / 1000.0,
             hole_radius_mm: radius_arg,
             hole_radius: radius_arg / 1000.0,
-            porosity: porosity_arg,
+            hole_shape,
+            hydraulic_radius: hole_shape.hydraulic_radius(radius_arg) / 1000.0,
+            end_correction: end_correction_mm / 1000.0,
+            hole_pattern,
+            porosity,
         })
     }
+
+    /*******************************************************************************************************************
+     * Combinations of argument values that can be calculated, but whose results should be treated with caution
+     */
+    pub fn warnings(&self) -> Vec<GenericError> {
+        validation::porosity_warning("porosity", self.porosity, self.geometric_porosity())
+            .into_iter()
+            .collect()
+    }
+
+    // The porosity of the hole pattern, which is used unless an explicit porosity is supplied
+    pub fn geometric_porosity(&self) -> f64 {
+        self.hole_pattern
+            .porosity(self.hole_shape.area(self.hole_radius_mm), self.hole_centres_mm)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+
+    const TOLERANCE: f64 = 1.0e-6;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < TOLERANCE, "expected {}, got {}", expected, actual);
+    }
+
+    #[test]
+    fn porosity_of_each_pattern() {
+        // Circular holes of radius 1mm on 10mm centres
+        let cases = [
+            (HolePattern::Square, PI / 100.0),
+            (HolePattern::Hexagonal, PI / (100.0 * sqrt(3.0) / 2.0)),
+            (HolePattern::Staggered, PI / 50.0),
+            (HolePattern::Custom { open_area_pct: 12.5 }, 0.125),
+        ];
+
+        for (pattern, expected) in cases {
+            assert_close(pattern.porosity(HoleShape::Circular.area(1.0), 10.0), expected);
+        }
+    }
+
+    #[test]
+    fn spacing_of_each_pattern() {
+        // (pattern, nearest centres, row pitch) for 10mm hole centres
+        let cases = [
+            (HolePattern::Square, 10.0, 10.0),
+            (HolePattern::Hexagonal, 10.0, 8.660254),
+            (HolePattern::Staggered, 7.071068, 5.0),
+        ];
+
+        for (pattern, nearest_centres, row_pitch) in cases {
+            assert_close(pattern.nearest_centres(10.0), nearest_centres);
+            assert_close(pattern.row_pitch(10.0), row_pitch);
+        }
+    }
+
+    #[test]
+    fn area_and_hydraulic_radius_of_each_shape() {
+        // (shape, area, hydraulic radius) for a hole radius of 1mm
+        let cases = [
+            (HoleShape::Circular, PI, 1.0),
+            (HoleShape::Square, 4.0, 1.0),
+            (HoleShape::Slot { length_mm: 10.0 }, 20.0, 40.0 / 24.0),
+        ];
+
+        for (shape, area, hydraulic_radius) in cases {
+            assert_close(shape.area(1.0), area);
+            assert_close(shape.hydraulic_radius(1.0), hydraulic_radius);
+        }
+    }
+
+    #[test]
+    fn end_correction_of_each_shape() {
+        // Circular hole: 2r x 0.8(1 - 1.47√σ + 0.47√σ³) with r = 1mm and σ = 0.01
+        assert_close(HoleShape::Circular.end_correction(1.0, 0.01, 10.0), 1.365552);
+
+        // Square hole: as the circular hole of the same area, whose radius is √(4/π)mm, with σ = 0.04
+        assert_close(HoleShape::Square.end_correction(1.0, 0.04, 10.0), 1.281405);
+
+        // Slot: 2w x -ln(sin(πw/2b))/π with w = 2mm and b = 10mm
+        assert_close(HoleShape::Slot { length_mm: 5.0 }.end_correction(1.0, 0.2, 10.0), 1.495240);
+    }
+
+    #[test]
+    fn slot_end_correction_vanishes_as_slots_merge() {
+        let slot = HoleShape::Slot { length_mm: 5.0 };
+
+        assert!(slot.end_correction(4.99, 0.9, 10.0) < 0.01);
+        assert!(slot.end_correction(0.5, 0.05, 10.0) > slot.end_correction(1.0, 0.1, 10.0) / 2.0);
+    }
+
+    #[test]
+    fn max_width_of_each_shape_and_pattern() {
+        let slot = HoleShape::Slot { length_mm: 6.0 };
+        let short_slot = HoleShape::Slot { length_mm: 4.0 };
+
+        assert_close(HoleShape::Circular.max_width(&HolePattern::Staggered, 10.0), 7.071068);
+        assert_close(HoleShape::Square.max_width(&HolePattern::Square, 10.0), 10.0);
+        assert_close(HoleShape::Square.max_width(&HolePattern::Hexagonal, 10.0), 8.660254);
+        assert_close(HoleShape::Square.max_width(&HolePattern::Staggered, 10.0), 5.0);
+        assert_close(slot.max_width(&HolePattern::Staggered, 10.0), 5.0);
+        assert_close(short_slot.max_width(&HolePattern::Staggered, 10.0), 10.0);
+    }
+
+    #[test]
+    fn panel_uses_geometric_porosity() {
+        let panel =
+            PerforatedPanelConfig::new(10.0, 10.0, 1.0, HoleShape::Circular, HolePattern::Square, None).unwrap();
+
+        assert_close(panel.porosity, PI / 100.0);
+        assert_close(panel.end_correction * 1000.0, 1.187307);
+        assert!(panel.warnings().is_empty());
+    }
+
+    #[test]
+    fn touching_holes_are_rejected() {
+        let err =
+            PerforatedPanelConfig::new(10.0, 10.0, 5.0, HoleShape::Circular, HolePattern::Square, None).unwrap_err();
+
+        assert_eq!(err.code, ErrorCode::Inconsistent);
+        assert_eq!(err.field.as_deref(), Some("hole_radius_mm"));
+    }
 }
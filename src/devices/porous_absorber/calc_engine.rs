@@ -30,7 +30,7 @@ pub fn calculate_plot_points(config_set: &'_ ConfigSet) -> GenericDeviceInfo<'_>
     let cavity = &config_set.cavity_config;
     let porous = config_set.porous_config.as_ref().unwrap();
 
-    let abs_info = config_set.chart_config.frequencies.iter().fold(
+    let abs_info = config_set.chart_config.sample_frequencies.iter().fold(
         GenericDeviceInfo::new(DeviceType::RigidBackedPorousAbsorber, None, None, None, Some(porous), &cavity),
         |mut acc, frequency| {
             let (abs_no_air_gap, abs_air_gap) = calculate_plot_point(*frequency, &config_set);
@@ -55,15 +55,20 @@ pub fn calculate_plot_points(config_set: &'_ ConfigSet) -> GenericDeviceInfo<'_>
     );
 
     trace_boundary(TraceAction::Exit);
-    abs_info
+    abs_info.band_averaged(&config_set.chart_config)
 }
 
 /***********************************************************************************************************************
  * Reducer function to calculate the absorption of a porous absorber at a specific frequency
+ * Returns the absorption without then with an air gap
  */
-fn calculate_plot_point(frequency: f64, config_set: &ConfigSet) -> (f64, f64) {
-    let trace_boundary = make_boundary_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, "calculate_plot_point");
+pub fn calculate_plot_point(frequency: f64, config_set: &ConfigSet) -> (f64, f64) {
+    const FN_NAME: &str = "calculate_plot_point";
+    let trace_boundary = make_boundary_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, FN_NAME);
+    let trace = make_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, FN_NAME);
+
     trace_boundary(TraceAction::Enter);
+    explain::start_point(frequency);
 
     let air_cfg = &config_set.air_config;
     let cavity_cfg = &config_set.cavity_config;
@@ -79,44 +84,58 @@ fn calculate_plot_point(frequency: f64, config_set: &ConfigSet) -> (f64, f64) {
 
     // Wave number in air
     let k_air = wave_no_in_air(&air_cfg, frequency);
+    explain::record(&trace, "Wave number", k_air);
 
     // Characteristic absorber impedance and wave number
     let (z_abs, wave_no_abs) = absorber_props(&air_cfg, porous_cfg, &frequency);
     let wave_no_abs_y = k_air * sin_phi;
     let wave_no_abs_x = ((wave_no_abs * wave_no_abs) - (wave_no_abs_y * wave_no_abs_y)).sqrt();
+    explain::record(&trace, "Characteristic impedance", z_abs);
+    explain::record(&trace, "Complex wave number", wave_no_abs);
+    explain::record(&trace, "Complex wave number x", wave_no_abs_x);
 
     // Angle of propagation within porous layer
     let beta_porous = sin(cmplx_abs(wave_no_abs_y / wave_no_abs)) * DEGREES_PER_RADIAN;
+    explain::record(&trace, "Angle of propagation in absorber", beta_porous);
 
     // Intermediate term for porous impedance calculation
     let porous_wave_no = wave_no_abs * porous_cfg.thickness;
     let cot_porous_wave_no = porous_wave_no.cos() / porous_wave_no.sin();
+    explain::record(&trace, "cot(k cmplx_abs * t cmplx_abs)", cot_porous_wave_no);
 
     // Impedance at absorber surface
     let z_abs_surface = minus_i * z_abs * (wave_no_abs / wave_no_abs_x) * cot_porous_wave_no;
+    explain::record(&trace, "Impedance at absorber surface", z_abs_surface);
 
     // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
     // Calculate absorption coefficient for porous absorber with no air gap
     let abs_refl = difference_over_sum((z_abs_surface / air_cfg.impedance) * cos_phi, 1.0);
     let abs_alpha = reflectivity_as_alpha(abs_refl);
+    explain::record(&trace, "No air gap reflection", abs_refl);
+    explain::record(&trace, "No air gap absorption", abs_alpha);
 
     // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
     // Impedance values (with air gap)
     // X and Y components of the wave number in the air gap
     let wave_no_air_y = wave_no_abs * sin(beta_porous * RADIANS_PER_DEGREE);
     let wave_no_air_x = ((k_air * k_air) - (wave_no_air_y * wave_no_air_y)).sqrt();
+    explain::record(&trace, "Wave number in air gap x", wave_no_air_x);
 
     // Impedance at top of air gap (after passing through porous absorber)
     let temp_imp = k_air * cavity_cfg.air_gap;
     let air_gap_z = minus_i * air_cfg.impedance * (k_air / wave_no_air_x) * (cos(temp_imp) / sin(temp_imp));
+    explain::record(&trace, "Impedance at top of air gap", air_gap_z);
 
     // Impedance at top of porous absorber after passing through air gap
     let intermediate3 = minus_i * z_abs * cot_porous_wave_no;
     let abs_air_z = ((air_gap_z * intermediate3) + (z_abs * z_abs)) / (air_gap_z + intermediate3);
+    explain::record(&trace, "Impedance at top of absorber", abs_air_z);
 
     // Absorption coefficient for porous absorber with air gap
     let abs_air_refl = difference_over_sum((abs_air_z / air_cfg.impedance) * cos_phi, 1.0);
     let abs_air_alpha = reflectivity_as_alpha(abs_air_refl);
+    explain::record(&trace, "Air gap reflection", abs_air_refl);
+    explain::record(&trace, "Air gap absorption", abs_air_alpha);
 
     trace_boundary(TraceAction::Exit);
     (abs_alpha, abs_air_alpha)
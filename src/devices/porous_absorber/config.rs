@@ -3,6 +3,7 @@
  *
  * (c) Chris Whealy 2020, 2024
  */
+use libm::pow;
 use serde_derive::{Deserialize, Serialize};
 use super::GenericError;
 use crate::config::{constants, ranges::NamedRange};
@@ -12,20 +13,92 @@ use crate::config::{constants, ranges::NamedRange};
  */
 const THICKNESS_RANGE: NamedRange<u16> = NamedRange {
     name: constants::TXT_THICKNESS,
+    field: "absorber_thickness_mm",
     units: constants::UNITS_THICKNESS,
     min: 5,
     default: 30,
     max: 500,
 };
 
-const FLOW_RESISTIVITY_RANGE: NamedRange<u32> = NamedRange {
+pub const FLOW_RESISTIVITY_RANGE: NamedRange<u32> = NamedRange {
     name: constants::TXT_FLOW_RESISTIVITY,
-    units: constants::UNITS_THICKNESS,
+    field: "flow_resistivity",
+    units: constants::UNITS_FLOW_RESISTIVITY,
     min: 1000,
     default: 16500,
     max: 100000,
 };
 
+const DENSITY_RANGE: NamedRange<f64> = NamedRange {
+    name: constants::TXT_BULK_DENSITY,
+    field: "fibre_properties.density",
+    units: constants::UNITS_DENSITY,
+    min: 5.0,
+    default: 30.0,
+    max: 300.0,
+};
+
+const FIBRE_DIAMETER_RANGE: NamedRange<f64> = NamedRange {
+    name: constants::TXT_FIBRE_DIAMETER,
+    field: "fibre_properties.fibre_diameter_um",
+    units: constants::UNITS_FIBRE_DIAMETER,
+    min: 1.0,
+    default: 10.0,
+    max: 100.0,
+};
+
+/***********************************************************************************************************************
+ * Empirical relations for estimating flow resistivity from the bulk density and fibre diameter of a fibrous material
+ *
+ * Both take the form sigma = K * density^N / d^2 with d in metres
+ *
+ * Bies & Hansen (1980) was derived from measurements of glass and mineral fibre materials
+ * Garai & Pompoli (2005) was derived from measurements of polyester fibre materials
+ */
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlowResistivityEstimator {
+    BiesHansen,
+    GaraiPompoli,
+}
+
+impl FlowResistivityEstimator {
+    fn coefficients(&self) -> (f64, f64) {
+        match self {
+            FlowResistivityEstimator::BiesHansen => (3.18e-9, 1.53),
+            FlowResistivityEstimator::GaraiPompoli => (2.5989e-8, 1.404),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FibreProperties {
+    pub density: f64,
+    pub fibre_diameter_um: f64,
+    pub estimator: FlowResistivityEstimator,
+}
+
+impl FibreProperties {
+    /*******************************************************************************************************************
+     * The density and fibre diameter are range checked, then the estimate is rounded to the nearest integer.  The
+     * estimate itself is range checked only when it is used to configure a porous layer
+     */
+    pub fn estimate_flow_resistivity(&self) -> Result<u32, GenericError> {
+        if !DENSITY_RANGE.contains(self.density) {
+            return Err(GenericError::new_from_f64(DENSITY_RANGE, self.density));
+        }
+
+        if !FIBRE_DIAMETER_RANGE.contains(self.fibre_diameter_um) {
+            return Err(GenericError::new_from_f64(FIBRE_DIAMETER_RANGE, self.fibre_diameter_um));
+        }
+
+        let (k, n) = self.estimator.coefficients();
+        let diameter = self.fibre_diameter_um / 1.0e6;
+
+        Ok((k * pow(self.density, n) / (diameter * diameter)).round() as u32)
+    }
+}
+
 /***********************************************************************************************************************
  * Porous absorber configuration
  */
@@ -56,4 +129,14 @@ impl PorousLayerConfig {
             sigma: sigma_arg,
         })
     }
+
+    /*******************************************************************************************************************
+     * The estimated flow resistivity must still lie within FLOW_RESISTIVITY_RANGE
+     */
+    pub fn from_fibre_properties(
+        thickness_arg: u16,
+        fibre: &FibreProperties,
+    ) -> Result<PorousLayerConfig, GenericError> {
+        PorousLayerConfig::new(thickness_arg, fibre.estimate_flow_resistivity()?)
+    }
 }
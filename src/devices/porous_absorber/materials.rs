@@ -0,0 +1,161 @@
+/***********************************************************************************************************************
+ * Porous Absorber Calculator - Library of porous materials
+ *
+ * Flow resistivity values are typical of the generic material at the stated density; the values for any specific
+ * product should be taken from the manufacturer's data sheet
+ *
+ * (c) Chris Whealy 2020, 2024
+ */
+use serde_derive::Serialize;
+
+use crate::config::GenericError;
+
+/***********************************************************************************************************************
+ * Johnson-Champoux-Allard parameters
+ */
+#[derive(Debug, Serialize)]
+pub struct JcaParameters {
+    pub porosity: f64,
+    pub tortuosity: f64,
+    // Viscous characteristic length (μm)
+    pub viscous_length_um: f64,
+    // Thermal characteristic length (μm)
+    pub thermal_length_um: f64,
+}
+
+/***********************************************************************************************************************
+ * Porous material
+ */
+#[derive(Debug, Serialize)]
+pub struct PorousMaterial {
+    pub id: &'static str,
+    pub name: &'static str,
+    // Bulk density (kg/m^3)
+    pub density: f64,
+    // Flow resistivity (Pa.s/m^2)
+    pub flow_resistivity: u32,
+    pub jca: Option<JcaParameters>,
+}
+
+pub const MATERIALS: [PorousMaterial; 12] = [
+    PorousMaterial {
+        id: "mineral_wool_30",
+        name: "Mineral wool 30 kg/m³",
+        density: 30.0,
+        flow_resistivity: 8000,
+        jca: None,
+    },
+    PorousMaterial {
+        id: "mineral_wool_45",
+        name: "Mineral wool 45 kg/m³",
+        density: 45.0,
+        flow_resistivity: 13000,
+        jca: None,
+    },
+    PorousMaterial {
+        id: "mineral_wool_60",
+        name: "Mineral wool 60 kg/m³",
+        density: 60.0,
+        flow_resistivity: 20000,
+        jca: None,
+    },
+    PorousMaterial {
+        id: "mineral_wool_100",
+        name: "Mineral wool 100 kg/m³",
+        density: 100.0,
+        flow_resistivity: 40000,
+        jca: None,
+    },
+    PorousMaterial {
+        id: "glass_fibre_16",
+        name: "Glass fibre 16 kg/m³",
+        density: 16.0,
+        flow_resistivity: 7000,
+        jca: Some(JcaParameters {
+            porosity: 0.99,
+            tortuosity: 1.01,
+            viscous_length_um: 190.0,
+            thermal_length_um: 380.0,
+        }),
+    },
+    PorousMaterial {
+        id: "glass_fibre_32",
+        name: "Glass fibre 32 kg/m³",
+        density: 32.0,
+        flow_resistivity: 16500,
+        jca: Some(JcaParameters {
+            porosity: 0.98,
+            tortuosity: 1.02,
+            viscous_length_um: 100.0,
+            thermal_length_um: 200.0,
+        }),
+    },
+    PorousMaterial {
+        id: "glass_fibre_48",
+        name: "Glass fibre 48 kg/m³",
+        density: 48.0,
+        flow_resistivity: 25000,
+        jca: None,
+    },
+    PorousMaterial {
+        id: "polyester_fibre_20",
+        name: "Polyester fibre 20 kg/m³",
+        density: 20.0,
+        flow_resistivity: 4000,
+        jca: None,
+    },
+    PorousMaterial {
+        id: "polyester_fibre_40",
+        name: "Polyester fibre 40 kg/m³",
+        density: 40.0,
+        flow_resistivity: 11000,
+        jca: None,
+    },
+    PorousMaterial {
+        id: "melamine_foam",
+        name: "Melamine foam 9 kg/m³",
+        density: 9.0,
+        flow_resistivity: 10500,
+        jca: Some(JcaParameters {
+            porosity: 0.99,
+            tortuosity: 1.02,
+            viscous_length_um: 100.0,
+            thermal_length_um: 130.0,
+        }),
+    },
+    PorousMaterial {
+        id: "cotton_felt",
+        name: "Recycled cotton felt 50 kg/m³",
+        density: 50.0,
+        flow_resistivity: 12000,
+        jca: None,
+    },
+    PorousMaterial {
+        id: "open_cell_polyurethane",
+        name: "Open cell polyurethane foam 30 kg/m³",
+        density: 30.0,
+        flow_resistivity: 9000,
+        jca: Some(JcaParameters {
+            porosity: 0.97,
+            tortuosity: 2.52,
+            viscous_length_um: 37.0,
+            thermal_length_um: 119.0,
+        }),
+    },
+];
+
+pub fn find(material_id: &str) -> Option<&'static PorousMaterial> {
+    MATERIALS.iter().find(|material| material.id == material_id)
+}
+
+/***********************************************************************************************************************
+ * A material id takes precedence over an explicit flow resistivity value, but one of them must be given
+ */
+pub fn flow_resistivity(material_id: &Option<String>, flow_resistivity_arg: Option<u32>) -> Result<u32, GenericError> {
+    match material_id {
+        Some(id) => find(id)
+            .map(|material| material.flow_resistivity)
+            .ok_or_else(|| GenericError::new_material_err(id)),
+        None => flow_resistivity_arg.ok_or_else(GenericError::new_missing_flow_resistivity_err),
+    }
+}
@@ -5,21 +5,23 @@
  */
 pub mod calc_engine;
 pub mod config;
+pub mod materials;
 
-use serde_derive::Deserialize;
+use serde_derive::{Deserialize, Serialize};
 use calc_engine::calculate_plot_points;
 use wasm_bindgen::JsValue;
-pub use config::PorousLayerConfig;
+pub use config::{FibreProperties, PorousLayerConfig};
 
 use crate::{
     config::{
-        air::AirConfig, cavity::CavityConfig, chart::ChartConfig,
+        air::AirConfig, cavity::CavityConfig, chart::{ChartArgs, ChartConfig, DerivedValue},
+        constants,
         config_set::ConfigSet,
         sound::SoundConfig,
-        GenericError,
+        errors_to_js, ok_to_js, GenericError,
     },
     trace::{trace_flags::trace_flag_for, *},
-    devices::DeviceTypeArgs,
+    devices::{generic_device::GenericDeviceInfo, DeviceTypeArgs},
 };
 
 pub const MOD_NAME: &str = "devices::porous_absorber";
@@ -27,21 +29,184 @@ pub const MOD_NAME: &str = "devices::porous_absorber";
 /***********************************************************************************************************************
  * Values received from the client
  */
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct PorousAbsorberArgs {
-    pub absorber_thickness_mm: u16,
-    pub flow_resistivity: u32,
+    #[serde(flatten)]
+    pub porous: PorousLayerArgs,
     pub air_gap_mm: u16,
     pub angle: u16,
-    pub graph_start_freq: f64,
-    pub smooth_curve: bool,
-    pub subdivision: u16,
-    pub show_diagram: bool,
-    pub air_temp: i16,
+    #[serde(flatten)]
+    pub chart: ChartArgs,
+    pub air_temp: f64,
     pub air_pressure: f64,
+    #[serde(default)]
+    pub relative_humidity: f64,
+}
+
+impl DeviceTypeArgs for PorousAbsorberArgs {
+    fn chart_args(&self) -> &ChartArgs {
+        &self.chart
+    }
+
+    fn config_set_for_chart(
+        &self,
+        chart_config: Result<ChartConfig, GenericError>,
+    ) -> Result<ConfigSet, Vec<GenericError>> {
+        let mut errors: Vec<GenericError> = vec![];
+
+        let config_set = ConfigSet {
+            // Required configuration
+            air_config: AirConfig::new(self.air_temp, self.air_pressure, self.relative_humidity).unwrap_or_else(
+                |err: GenericError| {
+                    errors.push(err);
+                    AirConfig::default()
+                },
+            ),
+
+            cavity_config: CavityConfig::new(self.air_gap_mm).unwrap_or_else(|err: GenericError| {
+                errors.push(err);
+                CavityConfig::default()
+            }),
+
+            chart_config: chart_config.unwrap_or_else(|err: GenericError| {
+                errors.push(err);
+                ChartConfig::default()
+            }),
+
+            // Variable configuration
+            sound_config: Some(SoundConfig::new(self.angle).unwrap_or_else(|err: GenericError| {
+                errors.push(err);
+                SoundConfig::default()
+            })),
+
+            panel_config: None,
+
+            porous_config: Some(self.porous.porous_layer_config().unwrap_or_else(|err: GenericError| {
+                errors.push(err);
+                PorousLayerConfig::default()
+            })),
+        };
+
+        if errors.is_empty() {
+            Ok(config_set)
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn calculate(config_set: &ConfigSet) -> GenericDeviceInfo<'_> {
+        calculate_plot_points(config_set)
+    }
+
+    fn chart_title(config_set: &ConfigSet) -> String {
+        crate::chart::constants::chart_title_at_incident_angle(
+            crate::chart::constants::CHART_TITLE_OVERALL_ABS,
+            config_set.sound_config.as_ref().unwrap().angle,
+        )
+    }
+
+    fn derived_values(&self, config_set: &ConfigSet) -> Vec<DerivedValue> {
+        self.porous.derived_values(config_set)
+    }
+
+    fn numeric_fields(&self) -> Vec<(&'static str, f64)> {
+        let mut fields = self.porous.numeric_fields();
+
+        fields.extend([
+            ("air_gap_mm", self.air_gap_mm as f64),
+            ("angle", self.angle as f64),
+            ("air_temp", self.air_temp),
+            ("air_pressure", self.air_pressure),
+            ("relative_humidity", self.relative_humidity),
+        ]);
+
+        fields
+    }
+
+    fn set_numeric_field(&mut self, field_name: &str, value: f64) -> bool {
+        match field_name {
+            "air_gap_mm" => self.air_gap_mm = value.round() as u16,
+            "angle" => self.angle = value.round() as u16,
+            "air_temp" => self.air_temp = value,
+            "air_pressure" => self.air_pressure = value,
+            "relative_humidity" => self.relative_humidity = value,
+            _ => return self.porous.set_numeric_field(field_name, value),
+        }
+
+        true
+    }
+}
+
+/***********************************************************************************************************************
+ * Porous layer values received from the client, common to every device type having a porous absorber
+ *
+ * The flow resistivity of the layer is taken from a material id, estimated from fibre properties or given explicitly,
+ * in that order of precedence
+ */
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PorousLayerArgs {
+    pub absorber_thickness_mm: u16,
+    #[serde(default)]
+    pub flow_resistivity: Option<u32>,
+    #[serde(default)]
+    pub material_id: Option<String>,
+    #[serde(default)]
+    pub fibre_properties: Option<FibreProperties>,
 }
 
-impl DeviceTypeArgs for PorousAbsorberArgs {}
+impl PorousLayerArgs {
+    pub fn porous_layer_config(&self) -> Result<PorousLayerConfig, GenericError> {
+        match (&self.material_id, &self.fibre_properties) {
+            (None, Some(fibre)) => PorousLayerConfig::from_fibre_properties(self.absorber_thickness_mm, fibre),
+            _ => materials::flow_resistivity(&self.material_id, self.flow_resistivity)
+                .and_then(|sigma| PorousLayerConfig::new(self.absorber_thickness_mm, sigma)),
+        }
+    }
+
+    // A flow resistivity that was not supplied explicitly is reported back to the client
+    pub fn derived_values(&self, config_set: &ConfigSet) -> Vec<DerivedValue> {
+        if self.material_id.is_none() && self.fibre_properties.is_none() {
+            vec![]
+        } else {
+            vec![DerivedValue {
+                field: "flow_resistivity",
+                name: constants::TXT_FLOW_RESISTIVITY,
+                value: config_set.porous_config.as_ref().unwrap().sigma as f64,
+                units: constants::UNITS_FLOW_RESISTIVITY,
+            }]
+        }
+    }
+
+    pub fn numeric_fields(&self) -> Vec<(&'static str, f64)> {
+        let flow_resistivity = match (&self.material_id, &self.fibre_properties) {
+            (None, Some(fibre)) => fibre.estimate_flow_resistivity(),
+            _ => materials::flow_resistivity(&self.material_id, self.flow_resistivity),
+        };
+
+        vec![
+            ("absorber_thickness_mm", self.absorber_thickness_mm as f64),
+            (
+                "flow_resistivity",
+                flow_resistivity.ok().or(self.flow_resistivity).unwrap_or_default() as f64,
+            ),
+        ]
+    }
+
+    pub fn set_numeric_field(&mut self, field_name: &str, value: f64) -> bool {
+        match field_name {
+            "absorber_thickness_mm" => self.absorber_thickness_mm = value.round() as u16,
+            "flow_resistivity" => {
+                // An explicit value replaces the material's flow resistivity
+                self.flow_resistivity = Some(value.round() as u32);
+                self.material_id = None;
+                self.fibre_properties = None;
+            },
+            _ => return false,
+        }
+
+        true
+    }
+}
 
 /***********************************************************************************************************************
  * Handle incoming arguments for calculating the absorption of a rigid backed porous absorption device
@@ -50,75 +215,32 @@ pub fn prepare(arg_obj: PorousAbsorberArgs) -> JsValue {
     let trace_boundary = make_boundary_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, "prepare");
     trace_boundary(TraceAction::Enter);
 
-    // Empty return data structure
-    let mut error_msgs: Vec<String> = vec![];
-
-    // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
-    // Construct set of configuration structs
-    let config_set = ConfigSet {
-        // Required configuration
-        air_config: AirConfig::new(arg_obj.air_temp, arg_obj.air_pressure).unwrap_or_else(|err: GenericError| {
-            error_msgs.push(err.to_string());
-            AirConfig::default()
-        }),
-
-        cavity_config: CavityConfig::new(arg_obj.air_gap_mm).unwrap_or_else(|err: GenericError| {
-            error_msgs.push(err.to_string());
-            CavityConfig::default()
-        }),
-
-        chart_config: ChartConfig::new(
-            arg_obj.graph_start_freq,
-            arg_obj.smooth_curve,
-            arg_obj.subdivision,
-            arg_obj.show_diagram,
-        )
-            .unwrap_or_else(|err: GenericError| {
-                error_msgs.push(err.to_string());
-                ChartConfig::default()
-            }),
-
-        // Variable configuration
-        sound_config: Some(SoundConfig::new(arg_obj.angle).unwrap_or_else(|err: GenericError| {
-            error_msgs.push(err.to_string());
-            SoundConfig::default()
-        })),
+    // If the arguments are valid, then calculate the absorption values, plot the graph and return the chart
+    // information, else return the validation errors
+    let series_data = match arg_obj.config_set() {
+        Ok(config_set) => {
+            let (absorber_info, explanation) = explain::explained(|| calculate_plot_points(&config_set));
 
-        panel_config: None,
+            // Plot the graph
+            let mut chart_info = crate::chart::render::generic_device(
+                absorber_info,
+                &config_set.chart_config,
+                &PorousAbsorberArgs::chart_title(&config_set),
+            );
 
-        porous_config: Some(
-            PorousLayerConfig::new(arg_obj.absorber_thickness_mm, arg_obj.flow_resistivity).unwrap_or_else(
-                |err: GenericError| {
-                    error_msgs.push(err.to_string());
-                    PorousLayerConfig::default()
-                },
-            ),
-        ),
-    };
+            chart_info.derived_values = arg_obj.derived_values(&config_set);
+            chart_info.warnings = arg_obj.warnings(&config_set);
+            chart_info.explanation = explanation;
 
-    // If there are no error messages, then calculate the absorption values, plot the graph and return the placeholder
-    // value "Ok", else return the array of error messages
-    let series_data = if error_msgs.is_empty() {
-        let absorber_info = calculate_plot_points(&config_set);
-
-        // Plot the graph
-        let chart_info = crate::chart::render::generic_device(
-            absorber_info,
-            &config_set.chart_config,
-            &crate::chart::constants::chart_title_at_incident_angle(
-                crate::chart::constants::CHART_TITLE_OVERALL_ABS,
-                config_set.sound_config.as_ref().unwrap().angle,
-            ),
-        );
+            ok_to_js(&chart_info)
+        },
 
-        serde_wasm_bindgen::to_value(&chart_info).unwrap()
-    } else {
-        // Serialize the error message(s)
-        serde_wasm_bindgen::to_value(&error_msgs).unwrap()
+        // Serialize the errors
+        Err(errors) => errors_to_js(&errors),
     };
 
     trace_boundary(TraceAction::Exit);
 
-    // Return either the {X,Y} values of plot points or the error messages back to JavaScript
+    // Return either the {X,Y} values of plot points or the errors back to JavaScript
     series_data
 }
@@ -37,19 +37,19 @@ pub fn calculate_plot_points(config_set: &'_ ConfigSet) -> GenericDeviceInfo<'_>
     let end_correction_delta = -log(sin(TAU * panel.porosity / 4.0)) * 2.0 / TAU;
     let end_corrected_panel_thickness = panel.thickness + (2.0 * panel.slot_width * end_correction_delta);
 
-    trace(format!("End correction delta          = {}", end_correction_delta));
-    trace(format!("End corrected panel thickness = {}", end_corrected_panel_thickness));
+    explain::record(&trace, "End correction delta", end_correction_delta);
+    explain::record(&trace, "End corrected panel thickness", end_corrected_panel_thickness);
 
     // Calculate resistance terms
     let resistance_at_backing = porous.sigma as f64 * porous.thickness;
     let resistance_at_panel = resistance_at_backing * panel.porosity;
     let mass_term_for_air = end_corrected_panel_thickness * air.density / panel.porosity;
 
-    trace(format!("Resistance at backing = {}", resistance_at_backing));
-    trace(format!("Resistance at panel   = {}", resistance_at_panel));
-    trace(format!("Mass term for air     = {}", mass_term_for_air));
+    explain::record(&trace, "Resistance at backing", resistance_at_backing);
+    explain::record(&trace, "Resistance at panel", resistance_at_panel);
+    explain::record(&trace, "Mass term for air", mass_term_for_air);
 
-    let abs_info = config_set.chart_config.frequencies.iter().fold(
+    let abs_info = config_set.chart_config.sample_frequencies.iter().fold(
         GenericDeviceInfo::new(DeviceType::SlottedPanelAbsorber, Some(panel), None, None, Some(porous), &cavity),
         |mut acc, frequency| {
             let (abs_no_air_gap, abs_against_panel, abs_against_backing) = calculate_plot_point(
@@ -86,7 +86,7 @@ pub fn calculate_plot_points(config_set: &'_ ConfigSet) -> GenericDeviceInfo<'_>
     );
 
     trace_boundary(TraceAction::Exit);
-    abs_info
+    abs_info.band_averaged(&config_set.chart_config)
 }
 
 /***********************************************************************************************************************
@@ -105,6 +105,7 @@ fn calculate_plot_point(
     let trace = make_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, FN_NAME);
 
     trace_boundary(TraceAction::Enter);
+    explain::start_point(frequency);
 
     let air_cfg = &config_set.air_config;
     let cavity_cfg = &config_set.cavity_config;
@@ -117,33 +118,33 @@ fn calculate_plot_point(
     // Wave number in air and angular frequency
     let k_air = wave_no_in_air(&air_cfg, frequency);
     let omega = f_ang(frequency);
-    trace(format!("Wave number       = {}", k_air));
-    trace(format!("Angular frequency = {}", omega));
+    explain::record(&trace, "Wave number", k_air);
+    explain::record(&trace, "Angular frequency", omega);
 
     // Characteristic absorber impedance and wave number
     let (z_abs, wave_no_abs) = absorber_props(&air_cfg, porous_cfg, &frequency);
-    trace(format!("Characteristic impedance = {}", z_abs));
-    trace(format!("Complex wave number      = {}", wave_no_abs));
+    explain::record(&trace, "Characteristic impedance", z_abs);
+    explain::record(&trace, "Complex wave number", wave_no_abs);
 
     // Intermediate terms
     let inter1 = k_air * ec_panel_thickness;
     let cot_inter1 = inter1.cos() / inter1.sin();
-    trace(format!("cot(k air * t panel) = {}", cot_inter1));
+    explain::record(&trace, "cot(k air * t panel)", cot_inter1);
 
     let inter2 = k_air * cavity_cfg.air_gap;
     let cot_inter2 = inter2.cos() / inter2.sin();
-    trace(format!("cot(k cmplx_abs * t air) = {}", cot_inter2));
+    explain::record(&trace, "cot(k cmplx_abs * t air)", cot_inter2);
 
     let inter3 = wave_no_abs * porous_cfg.thickness;
     let cot_inter3 = inter3.cos() / inter3.sin();
-    trace(format!("cot(complex_wave_no * t cmplx_abs) = {}", cot_inter3));
+    explain::record(&trace, "cot(complex_wave_no * t cmplx_abs)", cot_inter3);
 
     let inter4 = wave_no_abs * (cavity_cfg.air_gap + porous_cfg.thickness);
     let cot_inter4 = inter4.cos() / inter4.sin();
-    trace(format!("cot(complex_wave_no * total depth) = {}", cot_inter4));
+    explain::record(&trace, "cot(complex_wave_no * total depth)", cot_inter4);
 
     let mass_term_for_slotted_panel = i * ((omega * mass_term_for_air) - (air_cfg.impedance * cot_inter1));
-    trace(format!("Mass term for air in slotted panel = {}", mass_term_for_slotted_panel));
+    explain::record(&trace, "Mass term for air in slotted panel", mass_term_for_slotted_panel);
 
     // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
     // Absorber against panel
@@ -155,12 +156,12 @@ fn calculate_plot_point(
     let abs_against_panel_refl = difference_over_sum(abs_against_panel_z3, air_cfg.impedance);
     let abs_against_panel_alpha = reflectivity_as_alpha(abs_against_panel_refl);
 
-    trace(format!("Absorber against panel z1 = {}", abs_against_panel_z1));
-    trace(format!("Absorber against panel z2 = {}", abs_against_panel_z2));
-    trace(format!("Overall impedance         = {}", abs_against_panel_z3));
+    explain::record(&trace, "Absorber against panel z1", abs_against_panel_z1);
+    explain::record(&trace, "Absorber against panel z2", abs_against_panel_z2);
+    explain::record(&trace, "Overall impedance", abs_against_panel_z3);
 
-    trace(format!("Absorber against panel reflection = {}", abs_against_panel_refl));
-    trace(format!("Absorber against panel absorption = {}", abs_against_panel_alpha));
+    explain::record(&trace, "Absorber against panel reflection", abs_against_panel_refl);
+    explain::record(&trace, "Absorber against panel absorption", abs_against_panel_alpha);
 
     // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
     // Absorber against backing
@@ -173,12 +174,12 @@ fn calculate_plot_point(
     let abs_against_backing_refl = difference_over_sum(abs_against_backing_z3, air_cfg.impedance);
     let abs_against_backing_alpha = reflectivity_as_alpha(abs_against_backing_refl);
 
-    trace(format!("Absorber against backing z1 = {}", abs_against_backing_z1));
-    trace(format!("Absorber against backing z2 = {}", abs_against_backing_z2));
-    trace(format!("Absorber against backing z3 = {}", abs_against_backing_z3));
+    explain::record(&trace, "Absorber against backing z1", abs_against_backing_z1);
+    explain::record(&trace, "Absorber against backing z2", abs_against_backing_z2);
+    explain::record(&trace, "Absorber against backing z3", abs_against_backing_z3);
 
-    trace(format!("Absorber against backing reflection = {}", abs_against_backing_refl));
-    trace(format!("Absorber against backing absorption = {}", abs_against_backing_alpha));
+    explain::record(&trace, "Absorber against backing reflection", abs_against_backing_refl);
+    explain::record(&trace, "Absorber against backing absorption", abs_against_backing_alpha);
 
     // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
     // Absorber with no air gap
@@ -188,11 +189,11 @@ fn calculate_plot_point(
     let no_air_gap_refl = difference_over_sum(no_air_gap_z2, air_cfg.impedance);
     let no_air_gap_alpha = reflectivity_as_alpha(no_air_gap_refl);
 
-    trace(format!("No air gap z1 = {}", no_air_gap_z1));
-    trace(format!("No air gap z2 = {}", no_air_gap_z2));
+    explain::record(&trace, "No air gap z1", no_air_gap_z1);
+    explain::record(&trace, "No air gap z2", no_air_gap_z2);
 
-    trace(format!("No air gap reflection = {}", no_air_gap_refl));
-    trace(format!("No air gap absorption = {}", no_air_gap_alpha));
+    explain::record(&trace, "No air gap reflection", no_air_gap_refl);
+    explain::record(&trace, "No air gap absorption", no_air_gap_alpha);
 
     trace_boundary(TraceAction::Exit);
     (no_air_gap_alpha, abs_against_panel_alpha, abs_against_backing_alpha)
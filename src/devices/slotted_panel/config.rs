@@ -5,13 +5,17 @@
  */
 use serde_derive::{Deserialize, Serialize};
 use super::GenericError;
-use crate::config::{constants, ranges::NamedRange};
+use crate::{
+    config::{constants, ranges::NamedRange},
+    utils::validation,
+};
 
 /***********************************************************************************************************************
  * Range check values
  */
 const THICKNESS_RANGE: NamedRange<f64> = NamedRange {
     name: constants::TXT_THICKNESS,
+    field: "panel_thickness_mm",
     units: constants::UNITS_THICKNESS,
     min: 1.0,
     default: 10.0,
@@ -20,6 +24,7 @@ const THICKNESS_RANGE: NamedRange<f64> = NamedRange {
 
 const DISTANCE_RANGE: NamedRange<f64> = NamedRange {
     name: constants::TXT_DISTANCE,
+    field: "slot_distance_mm",
     units: constants::UNITS_DISTANCE,
     min: 2.0,
     default: 25.4,
@@ -28,14 +33,13 @@ const DISTANCE_RANGE: NamedRange<f64> = NamedRange {
 
 const WIDTH_RANGE: NamedRange<f64> = NamedRange {
     name: constants::TXT_WIDTH,
+    field: "slot_width_mm",
     units: constants::UNITS_WIDTH,
     min: 1.0,
     default: 5.0,
     max: 50.0,
 };
 
-const DEFAULT_POROSITY: f64 = WIDTH_RANGE.default / (DISTANCE_RANGE.default + WIDTH_RANGE.default);
-
 /***********************************************************************************************************************
  * Slotted panel configuration
  */
@@ -52,20 +56,14 @@ pub struct SlottedPanelConfig {
 
 impl SlottedPanelConfig {
     pub fn default() -> SlottedPanelConfig {
-        SlottedPanelConfig::new(
-            THICKNESS_RANGE.default,
-            DISTANCE_RANGE.default,
-            WIDTH_RANGE.default,
-            DEFAULT_POROSITY,
-        )
-            .unwrap()
+        SlottedPanelConfig::new(THICKNESS_RANGE.default, DISTANCE_RANGE.default, WIDTH_RANGE.default, None).unwrap()
     }
 
     pub fn new(
         thickness_arg: f64,
         distance_arg: f64,
         width_arg: f64,
-        porosity_arg: f64,
+        porosity_arg: Option<f64>,
     ) -> Result<SlottedPanelConfig, GenericError> {
         if thickness_arg < THICKNESS_RANGE.min || thickness_arg > THICKNESS_RANGE.max {
             return Err(GenericError::new_from_f64(THICKNESS_RANGE, thickness_arg));
@@ -79,6 +77,9 @@ impl SlottedPanelConfig {
             return Err(GenericError::new_from_f64(WIDTH_RANGE, width_arg));
         }
 
+        let porosity =
+            validation::porosity("slotted_porosity", porosity_arg, geometric_porosity(width_arg, distance_arg))?;
+
         Ok(SlottedPanelConfig {
             thickness_mm: thickness_arg,
             thickness: thickness_arg / 1000.0,
@@ -86,7 +87,25 @@ impl SlottedPanelConfig {
             slot_distance: distance_arg / 1000.0,
             slot_width_mm: width_arg,
             slot_width: width_arg / 1000.0,
-            porosity: porosity_arg,
+            porosity,
         })
     }
+
+    /*******************************************************************************************************************
+     * Combinations of argument values that can be calculated, but whose results should be treated with caution
+     */
+    pub fn warnings(&self) -> Vec<GenericError> {
+        validation::porosity_warning(
+            "slotted_porosity",
+            self.porosity,
+            geometric_porosity(self.slot_width_mm, self.slot_distance_mm),
+        )
+        .into_iter()
+        .collect()
+    }
+}
+
+// Slots are separated by the slot distance
+pub fn geometric_porosity(width: f64, distance: f64) -> f64 {
+    width / (distance + width)
 }
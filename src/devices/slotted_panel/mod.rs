@@ -8,18 +8,22 @@ pub mod config;
 
 use calc_engine::calculate_plot_points;
 pub use config::SlottedPanelConfig;
-use serde_derive::Deserialize;
+use serde_derive::{Deserialize, Serialize};
 use wasm_bindgen::JsValue;
 
 use crate::{
     config::{
         air::AirConfig,
         cavity::CavityConfig,
-        chart::ChartConfig,
+        chart::{ChartArgs, ChartConfig, DerivedValue},
         config_set::{ConfigSet, PanelConfigSet},
-        GenericError,
+        errors_to_js, ok_to_js, GenericError,
+    },
+    devices::{
+        generic_device::GenericDeviceInfo,
+        porous_absorber::{PorousLayerArgs, PorousLayerConfig},
+        derived_porosity, DeviceTypeArgs,
     },
-    devices::{DeviceTypeArgs, porous_absorber::PorousLayerConfig},
     trace::{trace_flags::trace_flag_for, *},
 };
 
@@ -28,24 +32,160 @@ pub const MOD_NAME: &str = "devices::slotted_panel";
 /***********************************************************************************************************************
  * Values received from the client
  */
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct SlottedPanelArgs {
     pub panel_thickness_mm: f64,
     pub slot_distance_mm: f64,
     pub slot_width_mm: f64,
-    pub slotted_porosity: f64,
-    pub absorber_thickness_mm: u16,
-    pub flow_resistivity: u32,
+    #[serde(default)]
+    pub slotted_porosity: Option<f64>,
+    #[serde(flatten)]
+    pub porous: PorousLayerArgs,
     pub air_gap_mm: u16,
-    pub graph_start_freq: f64,
-    pub smooth_curve: bool,
-    pub subdivision: u16,
-    pub show_diagram: bool,
-    pub air_temp: i16,
+    #[serde(flatten)]
+    pub chart: ChartArgs,
+    pub air_temp: f64,
     pub air_pressure: f64,
+    #[serde(default)]
+    pub relative_humidity: f64,
 }
 
-impl DeviceTypeArgs for SlottedPanelArgs {}
+impl DeviceTypeArgs for SlottedPanelArgs {
+    fn chart_args(&self) -> &ChartArgs {
+        &self.chart
+    }
+
+    fn config_set_for_chart(
+        &self,
+        chart_config: Result<ChartConfig, GenericError>,
+    ) -> Result<ConfigSet, Vec<GenericError>> {
+        let mut errors: Vec<GenericError> = vec![];
+
+        // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+        // Construct set of configuration structs
+        let panel_config_set = PanelConfigSet {
+            panel_microperforated: None,
+            panel_perforated: None,
+            panel_slotted: Some(
+                SlottedPanelConfig::new(
+                    self.panel_thickness_mm,
+                    self.slot_distance_mm,
+                    self.slot_width_mm,
+                    self.slotted_porosity,
+                )
+                .unwrap_or_else(|err: GenericError| {
+                    errors.push(err);
+                    SlottedPanelConfig::default()
+                }),
+            ),
+        };
+
+        let config_set = ConfigSet {
+            // Required configuration
+            air_config: AirConfig::new(self.air_temp, self.air_pressure, self.relative_humidity).unwrap_or_else(
+                |err: GenericError| {
+                    errors.push(err);
+                    AirConfig::default()
+                },
+            ),
+
+            cavity_config: CavityConfig::new(self.air_gap_mm).unwrap_or_else(|err: GenericError| {
+                errors.push(err);
+                CavityConfig::default()
+            }),
+
+            chart_config: chart_config.unwrap_or_else(|err: GenericError| {
+                errors.push(err);
+                ChartConfig::default()
+            }),
+
+            // Variable configuration
+            sound_config: None,
+
+            panel_config: Some(panel_config_set),
+            porous_config: Some(self.porous.porous_layer_config().unwrap_or_else(|err: GenericError| {
+                errors.push(err);
+                PorousLayerConfig::default()
+            })),
+        };
+
+        if errors.is_empty() {
+            Ok(config_set)
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn calculate(config_set: &ConfigSet) -> GenericDeviceInfo<'_> {
+        calculate_plot_points(config_set)
+    }
+
+    fn chart_title(_config_set: &ConfigSet) -> String {
+        crate::chart::constants::CHART_TITLE_NORMAL_INCIDENCE.to_string()
+    }
+
+    fn derived_values(&self, config_set: &ConfigSet) -> Vec<DerivedValue> {
+        let panel = config_set.panel_config.as_ref().unwrap().panel_slotted.as_ref().unwrap();
+        let mut derived_values = self.porous.derived_values(config_set);
+
+        derived_values.push(derived_porosity(
+            "slotted_porosity",
+            config::geometric_porosity(panel.slot_width_mm, panel.slot_distance_mm),
+        ));
+
+        derived_values
+    }
+
+    fn warnings(&self, config_set: &ConfigSet) -> Vec<GenericError> {
+        config_set
+            .panel_config
+            .as_ref()
+            .unwrap()
+            .panel_slotted
+            .as_ref()
+            .unwrap()
+            .warnings()
+    }
+
+    fn numeric_fields(&self) -> Vec<(&'static str, f64)> {
+        let mut fields = vec![
+            ("panel_thickness_mm", self.panel_thickness_mm),
+            ("slot_distance_mm", self.slot_distance_mm),
+            ("slot_width_mm", self.slot_width_mm),
+            (
+                "slotted_porosity",
+                self.slotted_porosity
+                    .unwrap_or_else(|| config::geometric_porosity(self.slot_width_mm, self.slot_distance_mm)),
+            ),
+        ];
+
+        fields.extend(self.porous.numeric_fields());
+        fields.extend([
+            ("air_gap_mm", self.air_gap_mm as f64),
+            ("air_temp", self.air_temp),
+            ("air_pressure", self.air_pressure),
+            ("relative_humidity", self.relative_humidity),
+        ]);
+
+        fields
+    }
+
+    fn set_numeric_field(&mut self, field_name: &str, value: f64) -> bool {
+        match field_name {
+            "panel_thickness_mm" => self.panel_thickness_mm = value,
+            "slot_distance_mm" => self.slot_distance_mm = value,
+            "slot_width_mm" => self.slot_width_mm = value,
+            "slotted_porosity" => self.slotted_porosity = Some(value),
+            "air_gap_mm" => self.air_gap_mm = value.round() as u16,
+            "air_temp" => self.air_temp = value,
+            "air_pressure" => self.air_pressure = value,
+            "relative_humidity" => self.relative_humidity = value,
+            _ => return self.porous.set_numeric_field(field_name, value),
+        }
+
+        true
+    }
+}
 
 /***********************************************************************************************************************
  * Handle incoming arguments for calculating the absorption of a slotted panel absorption device
@@ -55,85 +195,32 @@ pub fn prepare(arg_obj: SlottedPanelArgs) -> JsValue {
 
     trace_boundary(TraceAction::Enter);
 
-    // Empty return data structure
-    let mut error_msgs: Vec<String> = vec![];
-
-    // - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
-    // Construct set of configuration structs
-    let panel_config_set = PanelConfigSet {
-        panel_microperforated: None,
-        panel_perforated: None,
-        panel_slotted: Some(
-            SlottedPanelConfig::new(
-                arg_obj.panel_thickness_mm,
-                arg_obj.slot_distance_mm,
-                arg_obj.slot_width_mm,
-                arg_obj.slotted_porosity,
-            )
-            .unwrap_or_else(|err: GenericError| {
-                error_msgs.push(err.to_string());
-                SlottedPanelConfig::default()
-            }),
-        ),
-    };
+    // If the arguments are valid, then calculate the absorption values, plot the graph and return the chart
+    // information, else return the validation errors
+    let series_data = match arg_obj.config_set() {
+        Ok(config_set) => {
+            let (absorber_info, explanation) = explain::explained(|| calculate_plot_points(&config_set));
 
-    let config_set = ConfigSet {
-        // Required configuration
-        air_config: AirConfig::new(arg_obj.air_temp, arg_obj.air_pressure).unwrap_or_else(|err: GenericError| {
-            error_msgs.push(err.to_string());
-            AirConfig::default()
-        }),
-
-        cavity_config: CavityConfig::new(arg_obj.air_gap_mm).unwrap_or_else(|err: GenericError| {
-            error_msgs.push(err.to_string());
-            CavityConfig::default()
-        }),
-
-        chart_config: ChartConfig::new(
-            arg_obj.graph_start_freq,
-            arg_obj.smooth_curve,
-            arg_obj.subdivision,
-            arg_obj.show_diagram,
-        )
-        .unwrap_or_else(|err: GenericError| {
-            error_msgs.push(err.to_string());
-            ChartConfig::default()
-        }),
-
-        // Variable configuration
-        sound_config: None,
-
-        panel_config: Some(panel_config_set),
-        porous_config: Some(
-            PorousLayerConfig::new(arg_obj.absorber_thickness_mm, arg_obj.flow_resistivity).unwrap_or_else(
-                |err: GenericError| {
-                    error_msgs.push(err.to_string());
-                    PorousLayerConfig::default()
-                },
-            ),
-        ),
-    };
+            // Plot the graph
+            let mut chart_info = crate::chart::render::generic_device(
+                absorber_info,
+                &config_set.chart_config,
+                &SlottedPanelArgs::chart_title(&config_set),
+            );
+
+            chart_info.derived_values = arg_obj.derived_values(&config_set);
+            chart_info.warnings = arg_obj.warnings(&config_set);
+            chart_info.explanation = explanation;
+
+            ok_to_js(&chart_info)
+        },
 
-    // If there are no error messages, then calculate the absorption values, plot the graph and return the placeholder
-    // value "Ok", else return the array of error messages
-    let series_data = if error_msgs.is_empty() {
-        let absorber_info = calculate_plot_points(&config_set);
-
-        // Plot the graph
-        let chart_info = crate::chart::render::generic_device(
-            absorber_info,
-            &config_set.chart_config,
-            crate::chart::constants::CHART_TITLE_NORMAL_INCIDENCE,
-        );
-
-        serde_wasm_bindgen::to_value(&chart_info).unwrap()
-    } else {
-        // Serialize the error message(s)
-        serde_wasm_bindgen::to_value(&error_msgs).unwrap()
+        // Serialize the errors
+        Err(errors) => errors_to_js(&errors),
     };
 
     trace_boundary(TraceAction::Exit);
 
-    // Return either the {X,Y} values of plot points or the error messages back to JavaScript
+    // Return either the {X,Y} values of plot points or the errors back to JavaScript
     series_data
 }
@@ -3,43 +3,73 @@
  *
  * (c) Chris Whealy 2020, 2024
  */
+mod analysis;
 mod chart;
 mod config;
+mod design_link;
 mod devices;
+pub mod project;
 mod trace;
 mod utils;
 
 use std::fmt::Debug;
 use serde::Deserialize;
+use serde_json::Value;
 use wasm_bindgen::prelude::*;
 
 use {
-    devices::DeviceTypeArgs,
-    trace::{make_boundary_trace_fn, make_trace_fn, trace_flags::trace_flag_for, TraceAction},
+    config::units::{self, UnitSystem},
+    devices::{
+        microperforated_panel::MicroperforatedPanelArgs, perforated_panel::PerforatedPanelArgs,
+        porous_absorber::PorousAbsorberArgs, slotted_panel::SlottedPanelArgs,
+    },
+    trace::{explain, make_boundary_trace_fn, make_trace_fn, trace_flags::trace_flag_for, TraceAction},
 };
 
 pub const MOD_NAME: &str = "lib";
 
 /***********************************************************************************************************************
- * Invoke calculation for generic absorption device
+ * Invoke calculation or analysis for generic absorption device
+ *
+ * Every entry point returns either {ok: <result>} or {errors: [<error>, ...]}, where each error identifies the field
+ * that failed validation
+ *
+ * If the arguments contain a "units" property, then lengths, temperatures and pressures are converted to metric before
+ * the arguments are deserialized, and the request is handled in those units
+ *
+ * If the arguments contain the property "explain": true, then a device's result also explains how it was calculated
  */
 fn handle_device<T>(wasm_arg_obj: JsValue, fn_name: &str, device_fn: Box<dyn Fn(T) -> JsValue>) -> JsValue
 where
-    T: DeviceTypeArgs +  for<'a> Deserialize<'a> + Debug,
+    T: for<'a> Deserialize<'a> + Debug,
 {
     let trace_active = trace_flag_for(MOD_NAME);
-    let trace_boundary =  make_boundary_trace_fn(trace_active, MOD_NAME, fn_name);
+    let trace_boundary = make_boundary_trace_fn(trace_active, MOD_NAME, fn_name);
 
     trace_boundary(TraceAction::Enter);
 
-    let result = match serde_wasm_bindgen::from_value::<T>(wasm_arg_obj) {
-        Ok(arg_obj) => {
+    let args = serde_wasm_bindgen::from_value::<Value>(wasm_arg_obj)
+        .map_err(|err| err.to_string())
+        .and_then(|mut args| {
+            let units = UnitSystem::of_request(&args).map_err(|err| err.to_string())?;
+            let explain_requested = explain::of_request(&args).map_err(|err| err.to_string())?;
+            units::args_to_metric(&mut args, units);
+
+            serde_json::from_value::<T>(args)
+                .map(|arg_obj| (units, explain_requested, arg_obj))
+                .map_err(|err| err.to_string())
+        });
+
+    let result = match args {
+        Ok((units, explain_requested, arg_obj)) => {
             make_trace_fn(trace_active, MOD_NAME, fn_name)(format!("{:?}", arg_obj));
-            device_fn(arg_obj)
+            units::with_request_units(units, || {
+                explain::with_explain_requested(explain_requested, || device_fn(arg_obj))
+            })
         },
         Err(err) => {
-            trace::error(err.to_string());
-            JsValue::undefined()
+            trace::error(MOD_NAME, &err);
+            config::errors_to_js(&[config::GenericError::new(config::ErrorCode::Malformed, err)])
         },
     };
 
@@ -78,3 +108,202 @@ pub fn perforated_panel(wasm_arg_obj: JsValue) -> JsValue {
 pub fn microperforated_panel(wasm_arg_obj: JsValue) -> JsValue {
     handle_device(wasm_arg_obj, "microperforated_panel", Box::new(devices::microperforated_panel::prepare))
 }
+
+/***********************************************************************************************************************
+ * Monte Carlo uncertainty analysis of each device type
+ */
+#[wasm_bindgen]
+pub fn porous_absorber_uncertainty(wasm_arg_obj: JsValue) -> JsValue {
+    handle_device(
+        wasm_arg_obj,
+        "porous_absorber_uncertainty",
+        Box::new(analysis::uncertainty::prepare::<PorousAbsorberArgs>),
+    )
+}
+
+#[wasm_bindgen]
+pub fn slotted_panel_uncertainty(wasm_arg_obj: JsValue) -> JsValue {
+    handle_device(
+        wasm_arg_obj,
+        "slotted_panel_uncertainty",
+        Box::new(analysis::uncertainty::prepare::<SlottedPanelArgs>),
+    )
+}
+
+#[wasm_bindgen]
+pub fn perforated_panel_uncertainty(wasm_arg_obj: JsValue) -> JsValue {
+    handle_device(
+        wasm_arg_obj,
+        "perforated_panel_uncertainty",
+        Box::new(analysis::uncertainty::prepare::<PerforatedPanelArgs>),
+    )
+}
+
+#[wasm_bindgen]
+pub fn microperforated_panel_uncertainty(wasm_arg_obj: JsValue) -> JsValue {
+    handle_device(
+        wasm_arg_obj,
+        "microperforated_panel_uncertainty",
+        Box::new(analysis::uncertainty::prepare::<MicroperforatedPanelArgs>),
+    )
+}
+
+/***********************************************************************************************************************
+ * Local sensitivity analysis of each device type
+ */
+#[wasm_bindgen]
+pub fn porous_absorber_sensitivity(wasm_arg_obj: JsValue) -> JsValue {
+    handle_device(
+        wasm_arg_obj,
+        "porous_absorber_sensitivity",
+        Box::new(analysis::sensitivity::prepare::<PorousAbsorberArgs>),
+    )
+}
+
+#[wasm_bindgen]
+pub fn slotted_panel_sensitivity(wasm_arg_obj: JsValue) -> JsValue {
+    handle_device(
+        wasm_arg_obj,
+        "slotted_panel_sensitivity",
+        Box::new(analysis::sensitivity::prepare::<SlottedPanelArgs>),
+    )
+}
+
+#[wasm_bindgen]
+pub fn perforated_panel_sensitivity(wasm_arg_obj: JsValue) -> JsValue {
+    handle_device(
+        wasm_arg_obj,
+        "perforated_panel_sensitivity",
+        Box::new(analysis::sensitivity::prepare::<PerforatedPanelArgs>),
+    )
+}
+
+#[wasm_bindgen]
+pub fn microperforated_panel_sensitivity(wasm_arg_obj: JsValue) -> JsValue {
+    handle_device(
+        wasm_arg_obj,
+        "microperforated_panel_sensitivity",
+        Box::new(analysis::sensitivity::prepare::<MicroperforatedPanelArgs>),
+    )
+}
+
+/***********************************************************************************************************************
+ * Fit the flow resistivity of a rigid backed porous absorber to measured absorption data
+ */
+#[wasm_bindgen]
+pub fn porous_absorber_fit_flow_resistivity(wasm_arg_obj: JsValue) -> JsValue {
+    handle_device(
+        wasm_arg_obj,
+        "porous_absorber_fit_flow_resistivity",
+        Box::new(analysis::characterisation::prepare),
+    )
+}
+
+/***********************************************************************************************************************
+ * Compare the absorption of several designs, each of which can be any device type
+ */
+#[wasm_bindgen]
+pub fn compare_designs(wasm_arg_obj: JsValue) -> JsValue {
+    handle_device(wasm_arg_obj, "compare_designs", Box::new(analysis::comparison::prepare))
+}
+
+/***********************************************************************************************************************
+ * Process transfer function measurements from a two-microphone impedance tube
+ */
+#[wasm_bindgen]
+pub fn impedance_tube(wasm_arg_obj: JsValue) -> JsValue {
+    handle_device(wasm_arg_obj, "impedance_tube", Box::new(analysis::impedance_tube::prepare))
+}
+
+/***********************************************************************************************************************
+ * List the porous materials whose id can be used in place of an explicit flow resistivity
+ */
+#[wasm_bindgen]
+pub fn porous_materials() -> JsValue {
+    config::ok_to_js(&devices::porous_absorber::materials::MATERIALS)
+}
+
+/***********************************************************************************************************************
+ * ISO 9613-1 atmospheric attenuation at the given frequencies
+ */
+#[wasm_bindgen]
+pub fn air_attenuation(wasm_arg_obj: JsValue) -> JsValue {
+    handle_device(wasm_arg_obj, "air_attenuation", Box::new(analysis::air_attenuation::prepare))
+}
+
+/***********************************************************************************************************************
+ * Chart view limits after zooming or panning
+ */
+#[wasm_bindgen]
+pub fn zoom_chart(wasm_arg_obj: JsValue) -> JsValue {
+    handle_device(wasm_arg_obj, "zoom_chart", Box::new(chart::view::zoom))
+}
+
+#[wasm_bindgen]
+pub fn pan_chart(wasm_arg_obj: JsValue) -> JsValue {
+    handle_device(wasm_arg_obj, "pan_chart", Box::new(chart::view::pan))
+}
+
+/***********************************************************************************************************************
+ * Project documents containing named designs
+ */
+#[wasm_bindgen]
+pub fn save_project(wasm_arg_obj: JsValue) -> JsValue {
+    handle_device(wasm_arg_obj, "save_project", Box::new(project::prepare_save))
+}
+
+#[wasm_bindgen]
+pub fn load_project(wasm_arg_obj: JsValue) -> JsValue {
+    handle_device(wasm_arg_obj, "load_project", Box::new(project::prepare_load))
+}
+
+#[wasm_bindgen]
+pub fn migrate_project(wasm_arg_obj: JsValue) -> JsValue {
+    handle_device(wasm_arg_obj, "migrate_project", Box::new(project::prepare_migrate))
+}
+
+/***********************************************************************************************************************
+ * Encode device arguments as a string for use in a URL, and decode them again
+ */
+#[wasm_bindgen]
+pub fn encode_design_link(wasm_arg_obj: JsValue) -> JsValue {
+    handle_device(wasm_arg_obj, "encode_design_link", Box::new(design_link::prepare_encode))
+}
+
+#[wasm_bindgen]
+pub fn decode_design_link(wasm_arg_obj: JsValue) -> JsValue {
+    handle_device(wasm_arg_obj, "decode_design_link", Box::new(design_link::prepare_decode))
+}
+
+/***********************************************************************************************************************
+ * Switch debug/trace output on or off for a module without rebuilding, and list the modules that can be traced
+ */
+#[wasm_bindgen]
+pub fn set_trace(module: String, enabled: bool) -> JsValue {
+    if trace::trace_flags::set_trace_flag(&module, enabled) {
+        config::ok_to_js(&trace::trace_flags::trace_modules())
+    } else {
+        config::errors_to_js(&[config::GenericError::new(
+            config::ErrorCode::UnknownId,
+            format!("Unknown trace module '{}'", module),
+        )
+        .for_field("module")])
+    }
+}
+
+#[wasm_bindgen]
+pub fn trace_modules() -> JsValue {
+    config::ok_to_js(&trace::trace_flags::trace_modules())
+}
+
+/***********************************************************************************************************************
+ * Native builds, such as the project file command line tool, can write the trace output of every module to a file
+ */
+#[cfg(not(target_arch = "wasm32"))]
+pub fn trace_to_file(path: &std::path::Path) -> std::io::Result<()> {
+    for module in trace::trace_flags::trace_modules() {
+        trace::trace_flags::set_trace_flag(module.module, true);
+    }
+
+    trace::sinks::trace_to_file(path)
+}
@@ -0,0 +1,441 @@
+/***********************************************************************************************************************
+ * Porous Absorber Calculator - Project documents
+ *
+ * A project document is a JSON file containing a set of named designs that can be saved, shared and loaded again.  Each
+ * design holds the full arguments of one device together with the air conditions under which it is calculated.
+ *
+ * Documents written by earlier versions of the calculator are migrated to the current schema when loaded.  Version 0 is
+ * a snapshot of the browser's local storage, in which each tab is held as an array of {id, value} objects.
+ *
+ * (c) Chris Whealy 2020, 2024
+ */
+use std::collections::HashSet;
+
+use serde_derive::{Deserialize, Serialize};
+use serde_json::{Map, Number, Value};
+use wasm_bindgen::JsValue;
+
+use crate::{
+    config::{air::AirConfig, result_to_js, ErrorCode, GenericError},
+    devices::DeviceArgs,
+    trace::{trace_flags::trace_flag_for, *},
+};
+
+pub const MOD_NAME: &str = "project";
+
+pub const SCHEMA_VERSION: u16 = 1;
+
+// Local storage key of each legacy tab and the device type to which it corresponds
+const LEGACY_TABS: [(&str, &str); 4] = [
+    ("rb_porous_absorber", "porous_absorber"),
+    ("slotted_panel", "slotted_panel"),
+    ("perforated_panel", "perforated_panel"),
+    ("microperforated_panel", "microperforated_panel"),
+];
+const LEGACY_CONFIG_TAB: &str = "configuration";
+
+/***********************************************************************************************************************
+ * Project document
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectMetadata {
+    pub name: String,
+    #[serde(default)]
+    pub author: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub created: Option<String>,
+    #[serde(default)]
+    pub modified: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct AirConditions {
+    pub air_temp: f64,
+    pub air_pressure: f64,
+    #[serde(default)]
+    pub relative_humidity: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NamedDesign {
+    pub name: String,
+    pub device: DeviceArgs,
+    pub air: AirConditions,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectDocument {
+    pub schema_version: u16,
+    #[serde(default)]
+    pub metadata: ProjectMetadata,
+    pub designs: Vec<NamedDesign>,
+}
+
+/***********************************************************************************************************************
+ * Serialize a project document as JSON, after checking that every design is valid
+ */
+pub fn save(document: Value) -> Result<String, Vec<GenericError>> {
+    let project = parse(document)?;
+    serde_json::to_string_pretty(&project).map_err(|err| vec![malformed(err)])
+}
+
+/***********************************************************************************************************************
+ * Parse a project document of any schema version, migrating it to the current version
+ */
+pub fn load(project_json: &str) -> Result<ProjectDocument, Vec<GenericError>> {
+    let document = serde_json::from_str::<Value>(project_json).map_err(|err| vec![malformed(err)])?;
+    let migrated = migrate(document).map_err(|err| vec![err])?;
+
+    parse(migrated)
+}
+
+/***********************************************************************************************************************
+ * Upgrade a project document to the current schema version
+ */
+pub fn migrate(document: Value) -> Result<Value, GenericError> {
+    // Local storage snapshots have no schema version
+    let version = match document.get("schema_version") {
+        Some(version) => version.as_u64().ok_or_else(|| {
+            GenericError::new(
+                ErrorCode::InvalidValue,
+                format!("Project schema version must be a positive integer, not '{}'", version),
+            )
+            .for_field("schema_version")
+        })?,
+        None => 0,
+    };
+
+    make_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, "migrate")(format!("Schema version {}", version));
+
+    match version {
+        0 => migrate_local_storage(&document),
+        v if v == SCHEMA_VERSION as u64 => Ok(document),
+        v => Err(GenericError::new(
+            ErrorCode::Unsupported,
+            format!(
+                "Project schema version {} is newer than the latest supported version {}",
+                v, SCHEMA_VERSION
+            ),
+        )
+        .for_field("schema_version")
+        .with_value(v as f64)
+        .with_limits(0.0, SCHEMA_VERSION as f64)),
+    }
+}
+
+/***********************************************************************************************************************
+ * Handle incoming requests from JavaScript
+ *
+ * A saved or migrated project is returned as a JSON string, and a loaded project as an object whose designs can be
+ * passed directly to the device functions
+ */
+pub fn prepare_save(project: Value) -> JsValue {
+    let trace_boundary = make_boundary_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, "prepare_save");
+    trace_boundary(TraceAction::Enter);
+
+    let result = result_to_js(&save(project));
+
+    trace_boundary(TraceAction::Exit);
+    result
+}
+
+pub fn prepare_load(project_json: String) -> JsValue {
+    let trace_boundary = make_boundary_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, "prepare_load");
+    trace_boundary(TraceAction::Enter);
+
+    let result = result_to_js(&load(&project_json));
+
+    trace_boundary(TraceAction::Exit);
+    result
+}
+
+pub fn prepare_migrate(project_json: String) -> JsValue {
+    let trace_boundary = make_boundary_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, "prepare_migrate");
+    trace_boundary(TraceAction::Enter);
+
+    let migrated = serde_json::from_str::<Value>(&project_json)
+        .map_err(malformed)
+        .and_then(migrate)
+        .and_then(|document| serde_json::to_string_pretty(&document).map_err(malformed))
+        .map_err(|err| vec![err]);
+
+    let result = result_to_js(&migrated);
+
+    trace_boundary(TraceAction::Exit);
+    result
+}
+
+//**********************************************************************************************************************
+// Private API
+//**********************************************************************************************************************
+
+/***********************************************************************************************************************
+ * Read a current version document, after copying the air conditions of each design into its device arguments
+ */
+fn parse(mut document: Value) -> Result<ProjectDocument, Vec<GenericError>> {
+    copy_air_conditions(&mut document);
+
+    let project = serde_json::from_value::<ProjectDocument>(document).map_err(|err| vec![malformed(err)])?;
+    let errors = validate(&project);
+
+    make_trace_fn(trace_flag_for(MOD_NAME), MOD_NAME, "parse")(format!(
+        "{} designs, {} errors",
+        project.designs.len(),
+        errors.len()
+    ));
+
+    if errors.is_empty() {
+        Ok(project)
+    } else {
+        Err(errors)
+    }
+}
+
+/***********************************************************************************************************************
+ * Check the document's schema version and its designs
+ *
+ * Errors relating to a particular design are reported against the path of that design and their messages are prefixed
+ * with the design's name
+ */
+fn validate(project: &ProjectDocument) -> Vec<GenericError> {
+    let mut errors: Vec<GenericError> = vec![];
+    let mut design_names: HashSet<&str> = HashSet::new();
+
+    if project.schema_version != SCHEMA_VERSION {
+        errors.push(
+            GenericError::new(
+                ErrorCode::Unsupported,
+                format!(
+                    "Project schema version must be {}, not '{}'",
+                    SCHEMA_VERSION, project.schema_version
+                ),
+            )
+            .for_field("schema_version")
+            .with_value(project.schema_version as f64),
+        );
+    }
+
+    for (idx, design) in project.designs.iter().enumerate() {
+        let air = design.air;
+
+        if let Err(err) = AirConfig::new(air.air_temp, air.air_pressure, air.relative_humidity) {
+            errors.push(err.within(&format!("designs.{}.air", idx)).in_context(&design.name));
+        } else if let Err(device_errs) = design.device.config_set() {
+            errors.extend(
+                device_errs
+                    .into_iter()
+                    .map(|err| err.within(&format!("designs.{}.device", idx)).in_context(&design.name)),
+            );
+        }
+    }
+
+    for (idx, design) in project.designs.iter().enumerate() {
+        if design.name.trim().is_empty() {
+            errors.push(
+                GenericError::new(ErrorCode::InvalidValue, String::from("Every design must have a name"))
+                    .for_field(&format!("designs.{}.name", idx)),
+            );
+        } else if !design_names.insert(&design.name) {
+            errors.push(
+                GenericError::new(
+                    ErrorCode::Inconsistent,
+                    format!("Design name '{}' is used more than once", design.name),
+                )
+                .for_field(&format!("designs.{}.name", idx)),
+            );
+        }
+    }
+
+    errors
+}
+
+// The document is not valid JSON or does not have the structure of a project
+fn malformed(err: serde_json::Error) -> GenericError {
+    GenericError::new(ErrorCode::Malformed, err.to_string())
+}
+
+/***********************************************************************************************************************
+ * The air conditions of each design take precedence over any air values held in its device arguments, so a document
+ * need only specify them once
+ */
+fn copy_air_conditions(document: &mut Value) {
+    if let Some(designs) = document.get_mut("designs").and_then(Value::as_array_mut) {
+        for design in designs.iter_mut() {
+            if let Some(air) = design.get("air").and_then(Value::as_object).cloned() {
+                if let Some(device) = design.get_mut("device").and_then(Value::as_object_mut) {
+                    device.extend(air);
+                }
+            }
+        }
+    }
+}
+
+/***********************************************************************************************************************
+ * Convert a snapshot of the browser's local storage into a version 1 document
+ *
+ * Each tab found in local storage becomes a design named after its device type, calculated under the air conditions
+ * held in the configuration tab
+ */
+fn migrate_local_storage(snapshot: &Value) -> Result<Value, GenericError> {
+    let not_a_snapshot = || {
+        GenericError::new(
+            ErrorCode::Malformed,
+            String::from("Project document has no schema version and is not a snapshot of local storage"),
+        )
+    };
+
+    let snapshot = snapshot.as_object().ok_or_else(not_a_snapshot)?;
+    let air = snapshot.get(LEGACY_CONFIG_TAB).map(legacy_fields).unwrap_or_default();
+
+    let designs: Vec<Value> = LEGACY_TABS
+        .iter()
+        .filter_map(|(tab_name, device_type)| {
+            snapshot.get(*tab_name).map(|tab| {
+                let mut device = legacy_fields(tab);
+                device.insert(String::from("device_type"), Value::from(*device_type));
+
+                let mut design = Map::new();
+                design.insert(String::from("name"), Value::from(*device_type));
+                design.insert(String::from("device"), Value::Object(device));
+                design.insert(String::from("air"), Value::Object(air.clone()));
+
+                Value::Object(design)
+            })
+        })
+        .collect();
+
+    if designs.is_empty() {
+        return Err(not_a_snapshot());
+    }
+
+    let mut document = Map::new();
+    document.insert(String::from("schema_version"), Value::from(1));
+    document.insert(
+        String::from("metadata"),
+        serde_json::to_value(ProjectMetadata::default()).unwrap(),
+    );
+    document.insert(String::from("designs"), Value::from(designs));
+
+    Ok(Value::Object(document))
+}
+
+// Fields are held as an array of {id, value} objects
+fn legacy_fields(tab: &Value) -> Map<String, Value> {
+    tab.as_array()
+        .map(|fields| {
+            fields
+                .iter()
+                .filter_map(|field| match (field.get("id").and_then(Value::as_str), field.get("value")) {
+                    (Some(id), Some(value)) => Some((String::from(id), legacy_value(value))),
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Values may have been stored as strings, and whole numbers must become integers so that they can be read into integer
+// arguments
+fn legacy_value(value: &Value) -> Value {
+    let whole_number = |n: f64| {
+        if n.fract() == 0.0 && n.abs() < i64::MAX as f64 {
+            Value::from(n as i64)
+        } else {
+            Number::from_f64(n).map(Value::Number).unwrap_or(Value::Null)
+        }
+    };
+
+    match value {
+        Value::String(text) => match text.trim() {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            trimmed => trimmed.parse::<f64>().map(whole_number).unwrap_or_else(|_| value.clone()),
+        },
+        Value::Number(n) => n.as_f64().map(whole_number).unwrap_or_else(|| value.clone()),
+        _ => value.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn local_storage_snapshot() -> Value {
+        json!({
+            "configuration": [
+                { "id": "air_temp", "value": "20" },
+                { "id": "air_pressure", "value": "1" },
+            ],
+            "rb_porous_absorber": [
+                { "id": "absorber_thickness_mm", "value": "30" },
+                { "id": "flow_resistivity", "value": "16000" },
+                { "id": "air_gap_mm", "value": "100" },
+                { "id": "angle", "value": "0" },
+                { "id": "graph_start_freq", "value": "62.5" },
+                { "id": "smooth_curve", "value": "false" },
+                { "id": "subdivision", "value": "1" },
+                { "id": "show_diagram", "value": "false" },
+            ],
+        })
+    }
+
+    #[test]
+    fn migrates_local_storage_snapshot() {
+        let migrated = migrate(local_storage_snapshot()).unwrap();
+
+        assert_eq!(migrated["schema_version"], json!(SCHEMA_VERSION));
+        assert_eq!(migrated["designs"].as_array().unwrap().len(), 1);
+
+        let design = &migrated["designs"][0];
+        assert_eq!(design["name"], json!("porous_absorber"));
+        assert_eq!(design["air"], json!({ "air_temp": 20, "air_pressure": 1 }));
+        assert_eq!(design["device"]["device_type"], json!("porous_absorber"));
+        assert_eq!(design["device"]["flow_resistivity"], json!(16000));
+        assert_eq!(design["device"]["graph_start_freq"], json!(62.5));
+        assert_eq!(design["device"]["smooth_curve"], json!(false));
+    }
+
+    #[test]
+    fn migrated_snapshot_survives_save_and_load() {
+        let loaded = load(&local_storage_snapshot().to_string()).unwrap();
+        let saved = save(serde_json::to_value(&loaded).unwrap()).unwrap();
+        let reloaded = load(&saved).unwrap();
+
+        assert_eq!(reloaded.designs.len(), 1);
+        assert_eq!(reloaded.designs[0].air.air_temp, 20.0);
+        assert_eq!(serde_json::to_value(&reloaded).unwrap(), serde_json::to_value(&loaded).unwrap());
+    }
+
+    #[test]
+    fn current_version_is_unchanged() {
+        let migrated = migrate(local_storage_snapshot()).unwrap();
+
+        assert_eq!(migrate(migrated.clone()).unwrap(), migrated);
+    }
+
+    #[test]
+    fn rejects_newer_version() {
+        let err = migrate(json!({ "schema_version": SCHEMA_VERSION + 1, "designs": [] })).unwrap_err();
+
+        assert_eq!(err.code, ErrorCode::Unsupported);
+        assert_eq!(err.field.as_deref(), Some("schema_version"));
+    }
+
+    #[test]
+    fn rejects_invalid_version() {
+        let err = migrate(json!({ "schema_version": "1", "designs": [] })).unwrap_err();
+
+        assert_eq!(err.code, ErrorCode::InvalidValue);
+    }
+
+    #[test]
+    fn rejects_document_that_is_not_a_snapshot() {
+        let err = migrate(json!({ "designs": [] })).unwrap_err();
+
+        assert_eq!(err.code, ErrorCode::Malformed);
+    }
+}
@@ -0,0 +1,128 @@
+/***********************************************************************************************************************
+ * Trace Utility - Calculation explanations
+ *
+ * If a device request contains the property "explain": true, then the intermediate values traced by the calculation
+ * engine are also returned in the result as an explanation.  Values calculated once for the whole device are listed
+ * separately from those calculated at each frequency.
+ *
+ * Values are recorded only while a device's absorption curves are calculated, so the repeated calculations performed
+ * by an analysis are never explained
+ *
+ * (c) Chris Whealy 2020, 2024
+ */
+use std::cell::{Cell, RefCell};
+use std::fmt::Display;
+
+use num::complex::Complex;
+use serde::Serialize;
+use serde_json::Value;
+
+thread_local! {
+    static EXPLAIN_REQUESTED: Cell<bool> = const { Cell::new(false) };
+    static EXPLANATION: RefCell<Option<Explanation>> = const { RefCell::new(None) };
+}
+
+/***********************************************************************************************************************
+ * Explanation returned to the client
+ *
+ * Real values have no imaginary part
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct ExplainedValue {
+    pub name: &'static str,
+    pub re: f64,
+    pub im: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExplainedPoint {
+    pub freq: f64,
+    pub values: Vec<ExplainedValue>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Explanation {
+    pub device_values: Vec<ExplainedValue>,
+    pub points: Vec<ExplainedPoint>,
+}
+
+/***********************************************************************************************************************
+ * Intermediate values are either real or complex
+ */
+pub trait Explainable: Display {
+    fn parts(&self) -> (f64, Option<f64>);
+}
+
+impl Explainable for f64 {
+    fn parts(&self) -> (f64, Option<f64>) {
+        (*self, None)
+    }
+}
+
+impl Explainable for Complex<f64> {
+    fn parts(&self) -> (f64, Option<f64>) {
+        (self.re, Some(self.im))
+    }
+}
+
+/***********************************************************************************************************************
+ * Whether the client has asked for an explanation of the request currently being handled
+ */
+pub fn of_request(args: &Value) -> Result<bool, serde_json::Error> {
+    match args.get("explain") {
+        Some(explain) => serde_json::from_value(explain.clone()),
+        None => Ok(false),
+    }
+}
+
+pub fn with_explain_requested<R>(requested: bool, request_fn: impl FnOnce() -> R) -> R {
+    EXPLAIN_REQUESTED.with(|current| current.set(requested));
+    let result = request_fn();
+    EXPLAIN_REQUESTED.with(|current| current.set(false));
+
+    result
+}
+
+/***********************************************************************************************************************
+ * Perform a device calculation, recording its intermediate values if the client asked for an explanation
+ */
+pub fn explained<R>(calc_fn: impl FnOnce() -> R) -> (R, Option<Explanation>) {
+    if !EXPLAIN_REQUESTED.with(|requested| requested.get()) {
+        return (calc_fn(), None);
+    }
+
+    EXPLANATION.with(|explanation| *explanation.borrow_mut() = Some(Explanation::default()));
+    let result = calc_fn();
+
+    (result, EXPLANATION.with(|explanation| explanation.borrow_mut().take()))
+}
+
+// Values recorded from now on belong to this frequency
+pub fn start_point(freq: f64) {
+    EXPLANATION.with(|explanation| {
+        if let Some(explanation) = explanation.borrow_mut().as_mut() {
+            explanation.points.push(ExplainedPoint { freq, values: vec![] });
+        }
+    });
+}
+
+/***********************************************************************************************************************
+ * Trace an intermediate value and, when explaining, record it against the current frequency
+ *
+ * Values recorded before the first frequency belong to the device as a whole
+ */
+pub fn record<V: Explainable>(trace: &dyn Fn(String), name: &'static str, value: V) {
+    trace(format!("{} = {}", name, value));
+
+    EXPLANATION.with(|explanation| {
+        if let Some(explanation) = explanation.borrow_mut().as_mut() {
+            let (re, im) = value.parts();
+            let explained_value = ExplainedValue { name, re, im };
+
+            match explanation.points.last_mut() {
+                Some(point) => point.values.push(explained_value),
+                None => explanation.device_values.push(explained_value),
+            }
+        }
+    });
+}
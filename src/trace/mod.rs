@@ -1,22 +1,27 @@
 /***********************************************************************************************************************
  * Trace Utility - Trace crossing function boundary
  *
+ * Function boundaries are traced at the trace level and data at the debug level, with the module name as the target.
+ * The sinks prefix each message with its target, so the messages themselves do not repeat the module name
+ *
  * (c) Chris Whealy 2020, 2024
  */
 use std::fmt::Formatter;
 use wasm_bindgen::prelude::*;
 
+pub mod explain;
+pub mod sinks;
 pub mod trace_flags;
 
 /***********************************************************************************************************************
- * Interface to browser console.log() function
+ * Interface to browser console.log() and console.error() functions
  */
 #[wasm_bindgen]
 extern "C" {
-    #[wasm_bindgen(js_namespace = console)]
-    pub fn log(s: String);
-    #[wasm_bindgen(js_namespace = console)]
-    pub fn error(s: String);
+    #[wasm_bindgen(js_namespace = console, js_name = log)]
+    pub fn console_log(s: String);
+    #[wasm_bindgen(js_namespace = console, js_name = error)]
+    pub fn console_error(s: String);
 }
 
 #[allow(dead_code)]
@@ -43,10 +48,11 @@ impl std::fmt::Display for TraceAction {
  */
 pub fn make_boundary_trace_fn<'a>(is_active: bool, mod_name: &'a str, fn_name: &'a str) -> Box<dyn Fn(TraceAction) + 'a> {
     if is_active {
-        Box::new(
-            move |action: TraceAction| {
-                log(format!("{} {}.{}()", action, mod_name, fn_name));
-            })
+        sinks::init();
+
+        Box::new(move |action: TraceAction| {
+            log::trace!(target: mod_name, "{} {}()", action, fn_name);
+        })
     } else {
         Box::new(move |_| {})
     }
@@ -57,10 +63,20 @@ pub fn make_boundary_trace_fn<'a>(is_active: bool, mod_name: &'a str, fn_name: &
  */
 pub fn make_trace_fn<'a>(is_active: bool, mod_name: &'a str, fn_name: &'a str) -> Box<dyn Fn(String) + 'a> {
     if is_active {
+        sinks::init();
+
         Box::new(move |info| {
-            log(format!("WASM      {}.{}() {}", mod_name, fn_name, info));
+            log::debug!(target: mod_name, "{}() {}", fn_name, info);
         })
     } else {
         Box::new(move |_| {})
     }
 }
+
+/*********************************************************************************************************************
+ * Errors are reported irrespective of whether tracing is active
+ */
+pub fn error(mod_name: &str, info: &str) {
+    sinks::init();
+    log::error!(target: mod_name, "{}", info);
+}
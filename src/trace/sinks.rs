@@ -0,0 +1,103 @@
+/***********************************************************************************************************************
+ * Trace Utility - Destinations for trace output
+ *
+ * Trace output is emitted through the log crate, using the module name as the target.  In the browser, it is written
+ * to the console.  Native builds, such as test runs, write to stderr unless a file has been given.
+ *
+ * Any other logger can be used instead, provided it is installed before the first trace output is emitted
+ *
+ * (c) Chris Whealy 2020, 2024
+ */
+use std::sync::Once;
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+static INIT: Once = Once::new();
+
+/***********************************************************************************************************************
+ * Browser console
+ */
+#[cfg(target_arch = "wasm32")]
+pub struct ConsoleSink;
+
+#[cfg(target_arch = "wasm32")]
+static SINK: ConsoleSink = ConsoleSink;
+
+#[cfg(target_arch = "wasm32")]
+impl Log for ConsoleSink {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let line = format!("{} {}", record.target(), record.args());
+
+        match record.level() {
+            log::Level::Error => super::console_error(line),
+            _ => super::console_log(line),
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/***********************************************************************************************************************
+ * Standard error, or a file
+ */
+#[cfg(not(target_arch = "wasm32"))]
+pub struct WriterSink {
+    file: std::sync::Mutex<Option<std::fs::File>>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+static SINK: WriterSink = WriterSink {
+    file: std::sync::Mutex::new(None),
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Log for WriterSink {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        use std::io::Write;
+
+        let line = format!("{:<5} {} {}", record.level(), record.target(), record.args());
+
+        match self.file.lock().unwrap().as_mut() {
+            Some(file) => {
+                let _ = writeln!(file, "{}", line);
+            },
+            None => eprintln!("{}", line),
+        }
+    }
+
+    fn flush(&self) {
+        use std::io::Write;
+
+        if let Some(file) = self.file.lock().unwrap().as_mut() {
+            let _ = file.flush();
+        }
+    }
+}
+
+// Write trace output to a file rather than stderr
+#[cfg(not(target_arch = "wasm32"))]
+pub fn trace_to_file(path: &std::path::Path) -> std::io::Result<()> {
+    *SINK.file.lock().unwrap() = Some(std::fs::File::create(path)?);
+    init();
+
+    Ok(())
+}
+
+/***********************************************************************************************************************
+ * Install this module's sink, unless another logger has already been installed
+ */
+pub fn init() {
+    INIT.call_once(|| {
+        if log::set_logger(&SINK).is_ok() {
+            log::set_max_level(LevelFilter::Trace);
+        }
+    });
+}
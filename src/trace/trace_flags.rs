@@ -3,36 +3,132 @@
  *
  * (c) Chris Whealy 2020, 2024
  */
+use std::cell::RefCell;
+
+use serde_derive::Serialize;
+
 use crate::{
-    chart,
+    analysis, chart, design_link,
     devices::{microperforated_panel, perforated_panel, porous_absorber, slotted_panel},
+    project,
 };
 
 type TraceConfig<'a> = (bool, &'a str);
 
+#[derive(Debug, Serialize)]
+pub struct TraceModule {
+    pub module: &'static str,
+    pub enabled: bool,
+}
+
 /***********************************************************************************************************************
- * Switch runtime tracing on/off at a per-module level
+ * Initial state of runtime tracing at a per-module level
+ *
+ * These flags can then be switched on/off from JavaScript without rebuilding
  */
-const TRACE_CONFIG: [TraceConfig; 11] = [
+const TRACE_CONFIG: &[TraceConfig] = &[
     (false, crate::MOD_NAME),
+    (false, analysis::air_attenuation::MOD_NAME),
+    (false, analysis::characterisation::MOD_NAME),
+    (false, analysis::comparison::MOD_NAME),
+    (false, analysis::impedance_tube::MOD_NAME),
+    (false, analysis::sensitivity::MOD_NAME),
+    (false, analysis::uncertainty::MOD_NAME),
     (false, chart::render::MOD_NAME),
     (false, chart::render::draw::MOD_NAME),
+    (false, chart::view::MOD_NAME),
+    (false, design_link::MOD_NAME),
     (false, microperforated_panel::MOD_NAME),
     (false, microperforated_panel::calc_engine::MOD_NAME),
     (false, perforated_panel::MOD_NAME),
     (false, perforated_panel::calc_engine::MOD_NAME),
     (false, porous_absorber::MOD_NAME),
+    (false, project::MOD_NAME),
     (false, porous_absorber::calc_engine::MOD_NAME),
     (false, slotted_panel::MOD_NAME),
     (false, slotted_panel::calc_engine::MOD_NAME),
 ];
 
+const TRACE_MODULE_COUNT: usize = TRACE_CONFIG.len();
+
+thread_local! {
+    static TRACE_FLAGS: RefCell<[TraceConfig<'static>; TRACE_MODULE_COUNT]> = const { RefCell::new(initial_flags()) };
+}
+
 pub fn trace_flag_for(mod_name: &str) -> bool {
-    for tc in TRACE_CONFIG {
-        if tc.1 == mod_name {
-            return tc.0
+    TRACE_FLAGS.with(|flags| {
+        for tc in flags.borrow().iter() {
+            if tc.1 == mod_name {
+                return tc.0;
+            }
         }
+
+        false
+    })
+}
+
+// Returns false if there is no module of this name
+pub fn set_trace_flag(mod_name: &str, enabled: bool) -> bool {
+    TRACE_FLAGS.with(|flags| {
+        for tc in flags.borrow_mut().iter_mut() {
+            if tc.1 == mod_name {
+                tc.0 = enabled;
+                return true;
+            }
+        }
+
+        false
+    })
+}
+
+pub fn trace_modules() -> Vec<TraceModule> {
+    TRACE_FLAGS.with(|flags| {
+        flags
+            .borrow()
+            .iter()
+            .map(|tc| TraceModule { module: tc.1, enabled: tc.0 })
+            .collect()
+    })
+}
+
+//**********************************************************************************************************************
+// Private API
+//**********************************************************************************************************************
+const fn initial_flags() -> [TraceConfig<'static>; TRACE_MODULE_COUNT] {
+    let mut flags = [(false, ""); TRACE_MODULE_COUNT];
+    let mut idx = 0;
+
+    while idx < TRACE_MODULE_COUNT {
+        flags[idx] = TRACE_CONFIG[idx];
+        idx += 1;
     }
 
-    false
+    flags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn module_names_are_unique() {
+        let modules = trace_modules();
+
+        assert_eq!(modules.len(), TRACE_MODULE_COUNT);
+        for (idx, tm) in modules.iter().enumerate() {
+            let duplicated = modules[idx + 1..].iter().any(|other| other.module == tm.module);
+            assert!(!duplicated, "{}", tm.module);
+        }
+    }
+
+    #[test]
+    fn switches_flag_by_module_name() {
+        assert!(set_trace_flag(project::MOD_NAME, true));
+        assert!(trace_flag_for(project::MOD_NAME));
+        assert!(!trace_flag_for(design_link::MOD_NAME));
+
+        assert!(set_trace_flag(project::MOD_NAME, false));
+        assert!(!trace_flag_for(project::MOD_NAME));
+        assert!(!set_trace_flag("no_such_module", true));
+    }
 }
@@ -0,0 +1,95 @@
+/***********************************************************************************************************************
+ * Unpadded base64url encoding (RFC 4648 section 5) for values that must be carried in a URL
+ *
+ * (c) Chris Whealy 2020, 2024
+ */
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+pub fn encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let triple = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (idx, b)| acc | (*b as u32) << (16 - 8 * idx));
+
+        // A chunk of n bytes is encoded as n + 1 characters
+        for idx in 0..=chunk.len() {
+            encoded.push(ALPHABET[(triple >> (18 - 6 * idx) & 0x3f) as usize] as char);
+        }
+    }
+
+    encoded
+}
+
+// Returns None if the text contains a character outside the alphabet or has an impossible length
+pub fn decode(text: &str) -> Option<Vec<u8>> {
+    let sextets = text
+        .bytes()
+        .map(|c| ALPHABET.iter().position(|a| *a == c).map(|pos| pos as u32))
+        .collect::<Option<Vec<u32>>>()?;
+
+    if sextets.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut bytes = Vec::with_capacity(sextets.len() * 3 / 4);
+
+    for chunk in sextets.chunks(4) {
+        let triple = chunk.iter().enumerate().fold(0u32, |acc, (idx, s)| acc | s << (18 - 6 * idx));
+
+        for idx in 0..chunk.len() - 1 {
+            bytes.push((triple >> (16 - 8 * idx) & 0xff) as u8);
+        }
+    }
+
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test vectors from RFC 4648 section 10
+    #[test]
+    fn encodes_rfc_4648_vectors() {
+        let vectors = [
+            ("", ""),
+            ("f", "Zg"),
+            ("fo", "Zm8"),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg"),
+            ("fooba", "Zm9vYmE"),
+            ("foobar", "Zm9vYmFy"),
+        ];
+
+        for (plain, encoded) in vectors {
+            assert_eq!(encode(plain.as_bytes()), encoded);
+            assert_eq!(decode(encoded), Some(plain.as_bytes().to_vec()));
+        }
+    }
+
+    #[test]
+    fn uses_url_safe_alphabet() {
+        assert_eq!(encode(&[0xfb, 0xff]), "-_8");
+        assert_eq!(decode("-_8"), Some(vec![0xfb, 0xff]));
+    }
+
+    #[test]
+    fn round_trips_every_byte_value() {
+        let bytes: Vec<u8> = (0..=255).collect();
+
+        for len in 0..bytes.len() {
+            assert_eq!(decode(&encode(&bytes[..len])).as_deref(), Some(&bytes[..len]));
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_text() {
+        assert_eq!(decode("Zm9v+"), None);
+        assert_eq!(decode("Zm9v="), None);
+        assert_eq!(decode("Z"), None);
+        assert_eq!(decode("Zm9vY"), None);
+    }
+}
@@ -59,20 +59,30 @@ pub fn db_x(density: f64, frequency: f64, sigma: u32) -> f64 {
 }
 
 /***********************************************************************************************************************
- * Convert reflectivity to absorption and round to two decimal places
+ * Convert reflectivity to absorption
  * If the value is less than zero, then return 0.0
+ *
+ * The value is not rounded here because analyses such as sensitivity need the full precision.  Rounding to two decimal
+ * places happens only when plot points are handed back for display
  */
 pub fn reflectivity_as_alpha(refl: Complex<f64>) -> f64 {
     let alpha = 1.0 - pow(cmplx_abs(refl), 2.0);
 
-    // Ignore alpha values less than zero, else round to 2dp
+    // Ignore alpha values less than zero
     if alpha < 0.0 {
         0.0
     } else {
-        (alpha * 100.0).round() / 100.0
+        alpha
     }
 }
 
+/***********************************************************************************************************************
+ * Round an absorption value to two decimal places for display
+ */
+pub fn round_2dp(val: f64) -> f64 {
+    (val * 100.0).round() / 100.0
+}
+
 /***********************************************************************************************************************
  * Compute Bessel function of the first kind of integer order>=0 and complex argument z.
  *
@@ -1,2 +1,4 @@
+pub mod base64url;
 pub mod maths_functions;
+pub mod random;
 pub mod validation;
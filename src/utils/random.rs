@@ -0,0 +1,48 @@
+/***********************************************************************************************************************
+ * Pseudo-random number generation used by the Monte Carlo analyses
+ *
+ * (c) Chris Whealy 2020, 2024
+ */
+use libm::{cos, log, sqrt};
+
+use crate::utils::maths_functions::TAU;
+
+/***********************************************************************************************************************
+ * A small xorshift64* generator
+ *
+ * Statistical quality is more than adequate for sampling manufacturing tolerances, and using a fixed seed means that
+ * the same request always produces the same uncertainty bands
+ */
+pub struct Prng {
+    state: u64,
+}
+
+impl Prng {
+    pub fn new(seed: u64) -> Prng {
+        // The state must never be zero, so mix the seed with an arbitrary odd constant
+        Prng {
+            state: (seed ^ 0x9E37_79B9_7F4A_7C15) | 1,
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    // Uniformly distributed value in the half-open range [0, 1)
+    pub fn uniform(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    // Normally distributed value with a mean of zero and a standard deviation of one (Box-Muller transform)
+    pub fn standard_normal(&mut self) -> f64 {
+        // Avoid taking the log of zero
+        let u1 = 1.0 - self.uniform();
+        let u2 = self.uniform();
+
+        sqrt(-2.0 * log(u1)) * cos(TAU * u2)
+    }
+}
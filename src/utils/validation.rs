@@ -3,11 +3,101 @@
  *
  * (c) Chris Whealy 2020, 2024
  */
-use crate::config::ranges::NamedRange;
+use crate::config::{constants, ranges::NamedRange, units, ErrorCode, GenericError};
 
-pub fn failure_msg<T: std::fmt::Debug>(range: NamedRange<T>, err_val: T) -> String {
-    format!(
-        "{} must be a value in {} between {:?} and {:?}, not '{:?}'",
-        range.name, range.units, range.min, range.max, err_val
-    )
+// The porosity supplied by the client may differ from that calculated from the panel geometry by this fraction
+const POROSITY_TOLERANCE: f64 = 0.05;
+
+pub fn failure_msg<T: Into<f64> + Copy + std::fmt::Debug>(range: NamedRange<T>, err_val: T) -> String {
+    let (min, display_units) = units::display(range.units, range.min.into());
+
+    // Lengths, temperatures and pressures are quoted in the units of the client's request
+    if display_units == range.units {
+        format!(
+            "{} must be a value in {} between {:?} and {:?}, not '{:?}'",
+            range.name, range.units, range.min, range.max, err_val
+        )
+    } else {
+        format!(
+            "{} must be a value in {} between {:?} and {:?}, not '{:?}'",
+            range.name,
+            display_units,
+            min,
+            units::display_value(range.units, range.max.into()),
+            units::display_value(range.units, err_val.into())
+        )
+    }
+}
+
+/***********************************************************************************************************************
+ * Unless the client supplies a porosity, it is calculated from the panel geometry.  Either way, it must be greater than
+ * 0 and less than 1
+ */
+pub fn porosity(field: &str, porosity_arg: Option<f64>, geometric_porosity: f64) -> Result<f64, GenericError> {
+    let porosity = porosity_arg.unwrap_or(geometric_porosity);
+
+    if porosity > 0.0 && porosity < 1.0 {
+        Ok(porosity)
+    } else {
+        Err(GenericError::new(
+            ErrorCode::OutOfRange,
+            format!(
+                "{} must be a value greater than 0 and less than 1, not '{:?}'",
+                constants::TXT_POROSITY,
+                porosity
+            ),
+        )
+        .for_field(field)
+        .with_value(porosity)
+        .with_limits(0.0, 1.0))
+    }
+}
+
+/***********************************************************************************************************************
+ * Warn when the porosity supplied by the client disagrees with the porosity of the panel geometry
+ */
+pub fn porosity_warning(field: &str, porosity_arg: f64, geometric_porosity: f64) -> Option<GenericError> {
+    if (porosity_arg - geometric_porosity).abs() > geometric_porosity * POROSITY_TOLERANCE {
+        Some(
+            GenericError::new(
+                ErrorCode::Inconsistent,
+                format!(
+                    "Porosity '{:?}' does not match the porosity of {:.4} calculated from the panel geometry",
+                    porosity_arg, geometric_porosity
+                ),
+            )
+            .for_field(field)
+            .with_value(porosity_arg),
+        )
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn porosity_warning_fires_beyond_tolerance() {
+        assert!(porosity_warning("porosity", 0.1, 0.1).is_none());
+        assert!(porosity_warning("porosity", 0.104, 0.1).is_none());
+        assert!(porosity_warning("porosity", 0.096, 0.1).is_none());
+
+        for porosity_arg in [0.106, 0.094] {
+            let warning = porosity_warning("porosity", porosity_arg, 0.1).unwrap();
+
+            assert_eq!(warning.code, ErrorCode::Inconsistent);
+            assert_eq!(warning.field.as_deref(), Some("porosity"));
+            assert_eq!(warning.value, Some(porosity_arg));
+        }
+    }
+
+    #[test]
+    fn porosity_must_lie_between_0_and_1() {
+        assert_eq!(porosity("porosity", None, 0.2).unwrap(), 0.2);
+        assert_eq!(porosity("porosity", Some(0.3), 0.2).unwrap(), 0.3);
+        assert!(porosity("porosity", Some(1.0), 0.2).is_err());
+        assert!(porosity("porosity", None, 0.0).is_err());
+    }
 }